@@ -0,0 +1,279 @@
+use eframe::egui::{self, Color32};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
+use crate::collector::Snapshot;
+use crate::config::{AlertMetric, Threshold, UnitsConfig};
+
+/// How many level changes the alert log keeps.
+const LOG_CAPACITY: usize = 50;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Level {
+    pub fn color(self) -> Option<Color32> {
+        match self {
+            Level::Normal => None,
+            Level::Warning => Some(Color32::from_rgb(230, 180, 40)),
+            Level::Critical => Some(Color32::from_rgb(220, 60, 60)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Normal => "normal",
+            Level::Warning => "warning",
+            Level::Critical => "critical",
+        }
+    }
+}
+
+/// A level change of one reading.
+#[derive(Clone)]
+pub struct AlertEvent {
+    pub time: SystemTime,
+    pub key: String,
+    pub label: String,
+    pub metric: AlertMetric,
+    pub level: Level,
+    pub previous: Level,
+    pub value: f32,
+}
+
+impl AlertEvent {
+    pub fn describe(&self, units: &UnitsConfig) -> String {
+        let value = format_value(self.metric, self.value, units);
+        match self.level {
+            Level::Normal => format!("{} back to normal from {}: {}", self.label, self.previous.name(), value),
+            level => format!("{} {}: {}", self.label, level.name(), value),
+        }
+    }
+}
+
+/// Where one reading stands, and since when it has been at or above each level.
+#[derive(Default)]
+struct AlertState {
+    level: Level,
+    warning_since: Option<Instant>,
+    critical_since: Option<Instant>,
+}
+
+/// Tracks every thresholded reading, keyed like the history (`gpu.<uuid>.temperature`, `cpu.usage`, ...).
+pub struct Alerts {
+    thresholds: BTreeMap<AlertMetric, Threshold>,
+    states: HashMap<String, AlertState>,
+    log: VecDeque<AlertEvent>,
+}
+
+impl Alerts {
+    pub fn new(thresholds: BTreeMap<AlertMetric, Threshold>) -> Self {
+        Self {
+            thresholds,
+            states: HashMap::new(),
+            log: VecDeque::new(),
+        }
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: BTreeMap<AlertMetric, Threshold>) {
+        self.thresholds = thresholds;
+    }
+
+    /// Updates every reading in `snapshot` and returns how many level changes it caused; those are the newest
+    /// entries of the log. Readings the snapshot doesn't have a fresh value for are forgotten rather than kept
+    /// at a stale level.
+    pub fn evaluate(&mut self, snapshot: &Snapshot) -> usize {
+        let now = Instant::now();
+        let mut changed = 0;
+        let mut evaluated = HashSet::new();
+
+        for (metric, key, label, value) in readings(snapshot) {
+            let (Some(threshold), Some(value)) = (self.thresholds.get(&metric), value) else {
+                continue;
+            };
+            evaluated.insert(key.clone());
+
+            let state = self.states.entry(key.clone()).or_default();
+            let previous = state.level;
+            let level = state.update(threshold, value, now);
+            if level != previous {
+                if self.log.len() == LOG_CAPACITY {
+                    self.log.pop_back();
+                }
+                self.log.push_front(AlertEvent { time: SystemTime::now(), key, label, metric, level, previous, value });
+                changed += 1;
+            }
+        }
+        self.states.retain(|key, _| evaluated.contains(key));
+
+        changed
+    }
+
+    /// Keys of the readings that are critical right now.
+    pub fn critical(&self) -> HashSet<String> {
+        self.states
+            .iter()
+            .filter(|(_, state)| state.level == Level::Critical)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn level(&self, key: &str) -> Level {
+        self.states.get(key).map(|state| state.level).unwrap_or_default()
+    }
+
+    /// Colors the rest of the current row by the level of `key`.
+    pub fn tint(&self, ui: &mut egui::Ui, key: &str) {
+        if let Some(color) = self.level(key).color() {
+            ui.visuals_mut().override_text_color = Some(color);
+        }
+    }
+
+    /// Newest first.
+    pub fn log(&self) -> impl Iterator<Item = &AlertEvent> {
+        self.log.iter()
+    }
+}
+
+impl AlertState {
+    fn update(&mut self, threshold: &Threshold, value: f32, now: Instant) -> Level {
+        let level = self.level;
+        // Once a level is raised, it only clears after falling `hysteresis` below its threshold.
+        let exceeds = |limit: f32, raised: bool| match raised {
+            true => value > limit - threshold.hysteresis,
+            false => value >= limit,
+        };
+        let above_critical = exceeds(threshold.critical, level >= Level::Critical);
+        let above_warning = above_critical || exceeds(threshold.warning, level >= Level::Warning);
+
+        self.critical_since = above_critical.then(|| self.critical_since.unwrap_or(now));
+        self.warning_since = above_warning.then(|| self.warning_since.unwrap_or(now));
+
+        let sustain = Duration::from_secs(threshold.sustain_seconds);
+        let sustained = |since: Option<Instant>| since.is_some_and(|since| now.duration_since(since) >= sustain);
+        let level = if sustained(self.critical_since) || (level == Level::Critical && above_critical) {
+            Level::Critical
+        } else if sustained(self.warning_since) || (level >= Level::Warning && above_warning) {
+            Level::Warning
+        } else {
+            Level::Normal
+        };
+
+        self.level = level;
+        level
+    }
+}
+
+/// `(metric, key, label, value)` for every reading that has a threshold; `None` where it wasn't sampled.
+fn readings(snapshot: &Snapshot) -> Vec<(AlertMetric, String, String, Option<f32>)> {
+    let mut readings = Vec::new();
+
+    for gpu_stats in snapshot.gpu_stats.value().into_iter().flatten() {
+        let uuid = &gpu_stats.uuid;
+        readings.push((
+            AlertMetric::GpuTemperature,
+            format!("gpu.{uuid}.temperature"),
+            format!("GPU {} temperature", gpu_stats.index),
+            gpu_stats.temperature.value().map(|v| *v as f32),
+        ));
+        readings.push((
+            AlertMetric::GpuMemory,
+            format!("gpu.{uuid}.memory"),
+            format!("GPU {} VRAM", gpu_stats.index),
+            gpu_stats.used_memory_percentage.value().map(|v| *v as f32),
+        ));
+    }
+
+    readings.push((AlertMetric::CpuUsage, String::from("cpu.usage"), String::from("CPU usage"), snapshot.cpu_stats.total_usage.value().map(|v| *v as f32)));
+    readings.push((AlertMetric::CpuTemperature, String::from("cpu.temperature"), String::from("CPU temperature"), snapshot.cpu_stats.temperature.value().copied()));
+    readings.push((
+        AlertMetric::MemoryUsage,
+        String::from("memory.used"),
+        String::from("RAM usage"),
+        snapshot.memory_stats.value().map(|memory_stats| memory_stats.used_memory_percentage as f32),
+    ));
+
+    readings
+}
+
+fn format_value(metric: AlertMetric, value: f32, units: &UnitsConfig) -> String {
+    match metric.is_temperature() {
+        true => units.temperature.format(value, 0),
+        false => format!("{:.0}%", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryStats;
+    use crate::metric::Metric;
+
+    fn threshold(sustain_seconds: u64) -> Threshold {
+        Threshold { warning: 80.0, critical: 90.0, hysteresis: 5.0, sustain_seconds }
+    }
+
+    /// Feeds `(seconds since start, value)` readings and returns the level after each.
+    fn run(threshold: &Threshold, readings: &[(u64, f32)]) -> Vec<Level> {
+        let start = Instant::now();
+        let mut state = AlertState::default();
+        readings
+            .iter()
+            .map(|(seconds, value)| state.update(threshold, *value, start + Duration::from_secs(*seconds)))
+            .collect()
+    }
+
+    #[test]
+    fn raises_only_after_sustain_seconds() {
+        let levels = run(&threshold(10), &[(0, 95.0), (5, 95.0), (9, 95.0), (10, 95.0)]);
+        assert_eq!(levels, [Level::Normal, Level::Normal, Level::Normal, Level::Critical]);
+    }
+
+    #[test]
+    fn dropping_below_restarts_sustain() {
+        let levels = run(&threshold(10), &[(0, 95.0), (5, 50.0), (6, 95.0), (10, 95.0), (16, 95.0)]);
+        assert_eq!(levels, [Level::Normal, Level::Normal, Level::Normal, Level::Normal, Level::Critical]);
+    }
+
+    #[test]
+    fn does_not_flicker_inside_hysteresis_band() {
+        let levels = run(&threshold(0), &[(0, 91.0), (1, 88.0), (2, 90.5), (3, 86.0), (4, 89.0)]);
+        assert_eq!(levels, [Level::Critical; 5]);
+
+        let levels = run(&threshold(0), &[(0, 81.0), (1, 76.0), (2, 80.0), (3, 77.0)]);
+        assert_eq!(levels, [Level::Warning; 4]);
+    }
+
+    #[test]
+    fn steps_down_from_critical_through_warning_to_normal() {
+        let levels = run(&threshold(0), &[(0, 95.0), (1, 84.0), (2, 76.0), (3, 74.0), (4, 79.0)]);
+        assert_eq!(levels, [Level::Critical, Level::Warning, Level::Warning, Level::Normal, Level::Normal]);
+    }
+
+    #[test]
+    fn forgets_readings_the_collector_stopped_refreshing() {
+        let mut alerts = Alerts::new(BTreeMap::from([(AlertMetric::MemoryUsage, threshold(0))]));
+        let mut snapshot = Snapshot {
+            memory_stats: Metric::Value(MemoryStats { used_memory_percentage: 99, ..MemoryStats::default() }),
+            ..Snapshot::default()
+        };
+
+        assert_eq!(alerts.evaluate(&snapshot), 1);
+        assert_eq!(alerts.level("memory.used"), Level::Critical);
+
+        // The minimal layout dropped the memory panel; the stale 99% must not keep the alert raised.
+        snapshot.memory_stats = Metric::Unavailable(String::from("Memory panel disabled"));
+        assert_eq!(alerts.evaluate(&snapshot), 0);
+        assert_eq!(alerts.level("memory.used"), Level::Normal);
+    }
+
+    #[test]
+    fn skips_warning_when_jumping_straight_to_critical_and_back() {
+        let levels = run(&threshold(0), &[(0, 50.0), (1, 95.0), (2, 40.0)]);
+        assert_eq!(levels, [Level::Normal, Level::Critical, Level::Normal]);
+    }
+}
@@ -0,0 +1,831 @@
+use crate::alerts::Alerts;
+use crate::collector::{Collector, Scope, Snapshot};
+use crate::config::{Config, ConfigError, ConfigWatcher, HotkeyAction, Panel, ProcessSort, Theme};
+use crate::cpu;
+use crate::exporter;
+use crate::gpu::{GpuBackend, GpuStats, ThrottleReason};
+use crate::history::HistoryStore;
+use crate::hotkeys::Hotkeys;
+use crate::ipc;
+use crate::metric::Metric;
+use crate::notifier::NotifierThread;
+use crate::processes;
+use crate::recorder::Recorder;
+use crate::report;
+use crate::settings::{SettingsAction, SettingsView};
+use eframe::{egui, Frame};
+use eframe::egui::{Visuals, Color32, Rounding};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const CORE_GRID_COLUMNS: usize = 4;
+/// GPU processes listed per device, most VRAM first.
+const GPU_PROCESS_ROWS: usize = 5;
+/// How long a status message stays in the header.
+const STATUS_DURATION: Duration = Duration::from_secs(3);
+
+struct FpsCounter {
+    frames: u32,
+    last_fps_update: Instant,
+    current_fps: u32,
+}
+
+impl FpsCounter {
+    fn new() -> Self {
+        Self {
+            frames: 0,
+            last_fps_update: Instant::now(),
+            current_fps: 0,
+        }
+    }
+
+    fn update(&mut self) {
+        self.frames += 1;
+        if self.last_fps_update.elapsed() >= Duration::from_secs(1) {
+            self.current_fps = self.frames;
+            self.frames = 0;
+            self.last_fps_update = Instant::now();
+        }
+    }
+}
+
+pub struct StatsApp {
+    config: Config,
+    config_watcher: Option<ConfigWatcher>,
+    /// Why the last reload was rejected; the previous config stays in effect until the file is fixed.
+    config_error: Option<String>,
+    snapshot: Arc<Snapshot>,
+    history: HistoryStore,
+    alerts: Alerts,
+    notifier: Option<NotifierThread>,
+    /// UUIDs of the GPUs to show; all GPUs are shown while this is empty.
+    pinned_gpus: BTreeSet<String>,
+    /// Order of the processes panel, toggled from its header.
+    process_sort: ProcessSort,
+    collector: Collector,
+    fps_counter: FpsCounter,
+    visible: bool,
+    /// Index into `config.layouts` picked by the cycle-layout hotkey; `None` shows `config.panels`.
+    layout: Option<usize>,
+    click_through: bool,
+    recorder: Option<Recorder>,
+    /// Feedback for the last hotkey action, shown until it expires.
+    status: Option<(String, Instant)>,
+    /// Open while the settings view replaces the stats.
+    settings: Option<SettingsView>,
+    hotkeys: Hotkeys,
+    /// Commands received on the IPC socket.
+    remote_commands: Receiver<ipc::Command>,
+    /// Keeps the IPC socket file until the overlay exits.
+    _ipc_socket: Option<ipc::SocketFile>,
+}
+
+impl StatsApp {
+    pub fn new(ctx: &egui::Context, config: Config, config_path: Option<PathBuf>, gpu: Box<dyn GpuBackend>, record: bool) -> Self {
+        let exporting = config.exporter.listen.is_some();
+        let collector = Collector::spawn(gpu, &config, if exporting { Scope::full(&config) } else { Scope::from_config(&config) });
+        if let Some(listen) = config.exporter.listen {
+            if let Err(e) = exporter::spawn(listen, collector.shared()) {
+                eprintln!("Failed to start metrics exporter on {}: {}", listen, e);
+            }
+        }
+
+        let (command_sender, remote_commands) = mpsc::channel();
+        let mut ipc_socket = None;
+        if config.ipc.enabled {
+            let ctx = ctx.clone();
+            // Wakes the UI so a `show` takes effect even while hidden and repainting slowly.
+            let handler: ipc::CommandHandler = Arc::new(move |command| {
+                let _ = command_sender.send(command);
+                ctx.request_repaint();
+            });
+            let path = config.ipc.socket.clone().unwrap_or_else(ipc::default_socket_path);
+            match ipc::spawn(&path, collector.shared(), Some(handler)) {
+                Ok(socket_file) => ipc_socket = Some(socket_file),
+                Err(e) => eprintln!("Failed to open IPC socket {}: {}", path.display(), e),
+            }
+        }
+
+        let notifier = match config.notifications.enabled {
+            true => NotifierThread::spawn(config.notifications.clone())
+                .inspect_err(|e| eprintln!("Failed to start notifier: {}", e))
+                .ok(),
+            false => None,
+        };
+
+        let mut hotkeys = Hotkeys::new();
+        let config_error = hotkeys.bind(&config).err().map(|e| e.to_string());
+
+        let mut app = Self {
+            config_watcher: config_path.map(ConfigWatcher::new),
+            config_error,
+            snapshot: collector.latest(),
+            history: HistoryStore::new(config.history_length()),
+            alerts: Alerts::new(config.alerts.clone()),
+            notifier,
+            pinned_gpus: config.gpu.pinned.iter().cloned().collect(),
+            process_sort: config.processes.sort,
+            collector,
+            fps_counter: FpsCounter::new(),
+            visible: true,
+            layout: None,
+            click_through: false,
+            recorder: None,
+            status: None,
+            settings: None,
+            hotkeys,
+            remote_commands,
+            _ipc_socket: ipc_socket,
+            config,
+        };
+        if record {
+            app.toggle_logging();
+        }
+        app
+    }
+
+    fn reload_config(&mut self, ctx: &egui::Context) {
+        let Some(result) = self.config_watcher.as_mut().and_then(ConfigWatcher::poll) else {
+            return;
+        };
+
+        match result.and_then(|config| self.apply_config(ctx, config)) {
+            Ok(()) => self.config_error = None,
+            Err(e) => self.config_error = Some(e.to_string()),
+        }
+    }
+
+    /// Switches to `config` on the fly. Hotkeys that can't be registered show up as conflicts.
+    fn apply_config(&mut self, ctx: &egui::Context, config: Config) -> Result<(), ConfigError> {
+        self.hotkeys.bind(&config)?;
+
+        let (old_window, new_window) = (&self.config.window, &config.window);
+        if (old_window.width, old_window.height) != (new_window.width, new_window.height) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(new_window.width, new_window.height)));
+        }
+        if (old_window.x, old_window.y) != (new_window.x, new_window.y) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(new_window.x, new_window.y)));
+        }
+
+        if config.gpu.pinned != self.config.gpu.pinned {
+            self.pinned_gpus = config.gpu.pinned.iter().cloned().collect();
+        }
+        if config.processes.sort != self.config.processes.sort {
+            self.process_sort = config.processes.sort;
+        }
+
+        self.history.resize(config.history_length());
+        self.alerts.set_thresholds(config.alerts.clone());
+        if let Some(notifier) = &self.notifier {
+            notifier.set_config(config.notifications.clone());
+        }
+        self.config = config;
+        if self.layout.is_some_and(|layout| layout >= self.config.layouts.len()) {
+            self.layout = None;
+        }
+        self.apply_collector();
+        Ok(())
+    }
+
+    /// The config with the panels of the active layout preset, which decide what the collector samples.
+    fn active_config(&self) -> Config {
+        Config {
+            panels: self.panels().to_vec(),
+            ..self.config.clone()
+        }
+    }
+
+    /// Samples what the panels show, or everything while the exporter or a recording reads the snapshots too.
+    fn apply_collector(&self) {
+        let config = self.active_config();
+        let scope = match self.config.exporter.listen.is_some() || self.recorder.is_some() {
+            true => Scope::full(&config),
+            false => Scope::from_config(&config),
+        };
+        self.collector.apply(&config, scope);
+    }
+
+    fn panels(&self) -> &[Panel] {
+        match self.layout {
+            Some(layout) => &self.config.layouts[layout].panels,
+            None => &self.config.panels,
+        }
+    }
+
+    fn handle_hotkey(&mut self, ctx: &egui::Context, action: HotkeyAction) {
+        match action {
+            HotkeyAction::ToggleOverlay => self.visible = !self.visible,
+            HotkeyAction::Settings => match self.settings {
+                Some(_) => self.settings = None,
+                None => self.open_settings(),
+            },
+            HotkeyAction::CycleLayout => {
+                // Steps through the presets and then back to the configured panels.
+                self.layout = match self.layout {
+                    None if !self.config.layouts.is_empty() => Some(0),
+                    Some(layout) if layout + 1 < self.config.layouts.len() => Some(layout + 1),
+                    _ => None,
+                };
+                self.apply_collector();
+                let name = self.layout.map_or("default", |layout| &self.config.layouts[layout].name);
+                self.set_status(format!("Layout: {}", name));
+            }
+            HotkeyAction::ToggleLogging => self.toggle_logging(),
+            HotkeyAction::ResetHistory => {
+                self.history.clear();
+                self.set_status(String::from("Min/max reset"));
+            }
+            HotkeyAction::ToggleClickThrough => {
+                self.click_through = !self.click_through;
+                ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(self.click_through));
+                self.set_status(format!("Click-through {}", if self.click_through { "on" } else { "off" }));
+            }
+            HotkeyAction::CopySnapshot => {
+                ctx.copy_text(report::text(&self.snapshot, &self.config.units));
+                self.set_status(String::from("Snapshot copied"));
+            }
+        }
+    }
+
+    fn toggle_logging(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            self.set_status(format!("Saved {}", recorder.path().display()));
+            self.apply_collector();
+            return;
+        }
+
+        match Recorder::start(&self.config.recording) {
+            Ok(recorder) => {
+                self.set_status(format!("Logging to {}", recorder.path().display()));
+                self.recorder = Some(recorder);
+                self.apply_collector();
+            }
+            Err(e) => self.set_status(format!("Logging failed: {}", e)),
+        }
+    }
+
+    fn set_status(&mut self, status: String) {
+        self.status = Some((status, Instant::now()));
+    }
+
+    fn open_settings(&mut self) {
+        self.visible = true;
+        self.settings = Some(SettingsView::new(&self.config));
+    }
+
+    /// Validates, writes and applies the draft. On failure the settings view stays open with the error.
+    fn save_settings(&mut self, ctx: &egui::Context) {
+        let Some(settings) = self.settings.take() else {
+            return;
+        };
+
+        let draft = settings.draft.clone();
+        let saved = match &self.config_watcher {
+            Some(watcher) => draft.save(watcher.path()),
+            None => Err(ConfigError::Invalid(String::from("no config directory to save to"))),
+        };
+
+        match saved.and_then(|()| self.apply_config(ctx, draft)) {
+            Ok(()) => {
+                if let Some(watcher) = self.config_watcher.as_mut() {
+                    watcher.sync();
+                }
+                self.config_error = None;
+            }
+            Err(e) => {
+                self.settings = Some(SettingsView {
+                    error: Some(e.to_string()),
+                    ..settings
+                });
+            }
+        }
+    }
+
+    /// Picks up the collector's latest snapshot, recording it into history if it is new.
+    fn refresh_stats(&mut self) {
+        let snapshot = self.collector.latest();
+        if snapshot.sequence == self.snapshot.sequence {
+            return;
+        }
+
+        self.snapshot = snapshot;
+        self.record_history();
+        let changed = self.alerts.evaluate(&self.snapshot);
+        if let Some(notifier) = &self.notifier {
+            let events = self.alerts.log().take(changed).cloned().collect();
+            notifier.update(events, self.alerts.critical(), self.config.units.clone());
+        }
+
+        let fps = self.visible.then_some(self.fps_counter.current_fps);
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&self.snapshot, fps) {
+                self.recorder = None;
+                self.set_status(format!("Logging stopped: {}", e));
+            }
+        }
+    }
+
+    fn record_history(&mut self) {
+        for gpu_stats in self.snapshot.gpu_stats.value().into_iter().flatten() {
+            let uuid = &gpu_stats.uuid;
+            self.history.record(format!("gpu.{uuid}.utilization"), gpu_stats.utilization.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.memory_utilization"), gpu_stats.memory_utilization.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.encoder_utilization"), gpu_stats.encoder_utilization.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.decoder_utilization"), gpu_stats.decoder_utilization.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.memory"), gpu_stats.used_memory_percentage.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.temperature"), gpu_stats.temperature.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.core_clock"), gpu_stats.core_clock.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.memory_clock"), gpu_stats.memory_clock.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.fan_speed"), gpu_stats.fan_speed.value().map(|v| *v as f32));
+            self.history.record(format!("gpu.{uuid}.power_usage"), gpu_stats.power_usage.value().map(|v| *v as f32));
+        }
+
+        self.history.record(String::from("cpu.usage"), self.snapshot.cpu_stats.total_usage.value().map(|v| *v as f32));
+        self.history.record(String::from("cpu.temperature"), self.snapshot.cpu_stats.temperature.value().copied());
+        self.history.record(String::from("cpu.fan_speed"), self.snapshot.cpu_stats.fan_speed.value().map(|v| *v as f32));
+        let memory_stats = self.snapshot.memory_stats.value();
+        self.history.record(String::from("memory.used"), memory_stats.map(|memory_stats| memory_stats.used_memory_percentage as f32));
+        self.history.record(String::from("memory.swap"), memory_stats.map(|memory_stats| memory_stats.used_swap));
+    }
+
+    fn show_panel(&mut self, ui: &mut egui::Ui, panel: Panel) {
+        match panel {
+            Panel::Gpu => self.show_gpu(ui),
+            Panel::Cpu => self.show_cpu(ui),
+            Panel::CpuCores => self.show_cpu_cores(ui),
+            Panel::CpuTemperatures => self.show_cpu_temperatures(ui),
+            Panel::Fans => self.show_fans(ui),
+            Panel::Memory => self.show_memory(ui),
+            Panel::Processes => self.show_processes(ui),
+            Panel::Alerts => self.show_alerts(ui),
+            Panel::SelfStats => self.show_self_stats(ui),
+        }
+    }
+
+    fn show_gpu(&mut self, ui: &mut egui::Ui) {
+        let snapshot = Arc::clone(&self.snapshot);
+        let units = self.config.units.clone();
+        let all_gpu_stats = snapshot.gpu_stats.value().map(Vec::as_slice).unwrap_or_default();
+
+        if let Metric::Unavailable(_) = snapshot.gpu_stats {
+            ui.horizontal(|ui| {
+                ui.label("GPU:");
+                snapshot.gpu_stats.show(ui, |_| String::new());
+            });
+        } else if all_gpu_stats.is_empty() {
+            ui.label("GPU: not available");
+        } else if all_gpu_stats.len() > 1 {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Pin:");
+                for gpu_stats in all_gpu_stats {
+                    let pinned = self.pinned_gpus.contains(&gpu_stats.uuid);
+                    if ui.selectable_label(pinned, format!("{}", gpu_stats.index)).on_hover_text(&gpu_stats.uuid).clicked() {
+                        if pinned {
+                            self.pinned_gpus.remove(&gpu_stats.uuid);
+                        } else {
+                            self.pinned_gpus.insert(gpu_stats.uuid.clone());
+                        }
+                    }
+                }
+            });
+        }
+
+        for gpu_stats in all_gpu_stats {
+            if !self.pinned_gpus.is_empty() && !self.pinned_gpus.contains(&gpu_stats.uuid) {
+                continue;
+            }
+
+            egui::CollapsingHeader::new(format!("GPU {}: {}", gpu_stats.index, gpu_stats.name))
+                .id_salt(&gpu_stats.uuid)
+                .default_open(true)
+                .show(ui, |ui| {
+                    gpu_stats.pci_bus_id.show(ui, |pci_bus_id| pci_bus_id.clone()).on_hover_text(&gpu_stats.uuid);
+                    ui.horizontal(|ui| {
+                        ui.label("GPU Usage:");
+                        gpu_stats.utilization.show(ui, |utilization| format!("{}%", utilization));
+                        self.history.show(ui, &format!("gpu.{}.utilization", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Memory Controller:");
+                        gpu_stats.memory_utilization.show(ui, |utilization| format!("{}%", utilization));
+                        self.history.show(ui, &format!("gpu.{}.memory_utilization", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Encoder:");
+                        gpu_stats.encoder_utilization.show(ui, |utilization| format!("{}%", utilization));
+                        self.history.show(ui, &format!("gpu.{}.encoder_utilization", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Decoder:");
+                        gpu_stats.decoder_utilization.show(ui, |utilization| format!("{}%", utilization));
+                        self.history.show(ui, &format!("gpu.{}.decoder_utilization", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("VRAM Usage:");
+                        self.alerts.tint(ui, &format!("gpu.{}.memory", gpu_stats.uuid));
+                        gpu_stats.used_memory.show(ui, |used_memory| format!("{:.1}", used_memory));
+                        ui.label("/");
+                        gpu_stats.total_memory.show(ui, |total_memory| format!("{:.1} GB", total_memory));
+                        gpu_stats.used_memory_percentage.show(ui, |percentage| format!("({}%)", percentage));
+                        self.history.show(ui, &format!("gpu.{}.memory", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Temperature:");
+                        self.alerts.tint(ui, &format!("gpu.{}.temperature", gpu_stats.uuid));
+                        gpu_stats.temperature.show(ui, |temperature| units.temperature.format(*temperature as f32, 0));
+                        self.history.show(ui, &format!("gpu.{}.temperature", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Core Clock:");
+                        gpu_stats.core_clock.show(ui, |core_clock| format!("{} MHz", core_clock));
+                        self.history.show(ui, &format!("gpu.{}.core_clock", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("P-State:");
+                        gpu_stats.performance_state.show(ui, |state| format!("P{}", state));
+                        for reason in gpu_stats.throttle_reasons.value().into_iter().flatten() {
+                            show_throttle_badge(ui, *reason);
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Memory Clock:");
+                        gpu_stats.memory_clock.show(ui, |memory_clock| format!("{} MHz", memory_clock));
+                        self.history.show(ui, &format!("gpu.{}.memory_clock", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Fan Speed:");
+                        gpu_stats.fan_speed.show(ui, |fan_speed| format!("{}%", fan_speed));
+                        self.history.show(ui, &format!("gpu.{}.fan_speed", gpu_stats.uuid));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Power Usage:");
+                        gpu_stats.power_usage.show(ui, |power_usage| format!("{:.1} W", power_usage));
+                        self.history.show(ui, &format!("gpu.{}.power_usage", gpu_stats.uuid));
+                    });
+
+                    show_gpu_processes(ui, gpu_stats);
+                });
+        }
+    }
+
+    fn show_cpu(&mut self, ui: &mut egui::Ui) {
+        let cpu_stats = &self.snapshot.cpu_stats;
+
+        ui.horizontal(|ui| {
+            ui.label("CPU Usage:");
+            self.alerts.tint(ui, "cpu.usage");
+            cpu_stats.total_usage.show(ui, |usage| format!("{}%", usage));
+            self.history.show(ui, "cpu.usage");
+        });
+
+        if let Some(max_frequency) = cpu_stats.cores.value().and_then(|cores| cores.iter().map(|core| core.frequency).max()) {
+            ui.horizontal(|ui| {
+                ui.label("CPU Clock:");
+                ui.label(format!("{} MHz", max_frequency));
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("CPU Temperature:");
+            self.alerts.tint(ui, "cpu.temperature");
+            cpu_stats.temperature.show(ui, |temp| self.config.units.temperature.format(*temp, 1));
+            self.history.show(ui, "cpu.temperature");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("CPU Fan:");
+            cpu_stats.fan_speed.show(ui, |rpm| format!("{} RPM", rpm));
+            self.history.show(ui, "cpu.fan_speed");
+        });
+    }
+
+    fn show_cpu_cores(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("cpu_cores")
+            .num_columns(CORE_GRID_COLUMNS)
+            .spacing([2.0, 2.0])
+            .show(ui, |ui| {
+                for (index, core) in self.snapshot.cpu_stats.cores.value().into_iter().flatten().enumerate() {
+                    let fill = if core.usage >= cpu::SATURATED_CORE_USAGE {
+                        Color32::from_rgb(220, 60, 60)
+                    } else {
+                        Color32::from_rgb(70, 130, 180)
+                    };
+
+                    ui.add(egui::ProgressBar::new(core.usage / 100.0)
+                        .desired_width(54.0)
+                        .desired_height(10.0)
+                        .fill(fill))
+                        .on_hover_text(format!("Core {}: {:.0}% @ {} MHz", index, core.usage, core.frequency));
+
+                    if (index + 1) % CORE_GRID_COLUMNS == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
+    fn show_cpu_temperatures(&mut self, ui: &mut egui::Ui) {
+        if self.snapshot.cpu_stats.core_temperatures.is_empty() {
+            return;
+        }
+
+        egui::CollapsingHeader::new("Core Temperatures").show(ui, |ui| {
+            for (label, temp) in &self.snapshot.cpu_stats.core_temperatures {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", label));
+                    ui.label(self.config.units.temperature.format(*temp, 1));
+                });
+            }
+        });
+    }
+
+    fn show_fans(&mut self, ui: &mut egui::Ui) {
+        if self.snapshot.cpu_stats.fans.is_empty() {
+            return;
+        }
+
+        egui::CollapsingHeader::new("Fans").show(ui, |ui| {
+            for (label, rpm) in &self.snapshot.cpu_stats.fans {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", label));
+                    ui.label(format!("{} RPM", rpm));
+                });
+            }
+        });
+    }
+
+    fn show_memory(&mut self, ui: &mut egui::Ui) {
+        let memory_stats = match &self.snapshot.memory_stats {
+            Metric::Value(memory_stats) => memory_stats,
+            Metric::Unavailable(_) => {
+                ui.horizontal(|ui| {
+                    ui.label("RAM Usage:");
+                    self.snapshot.memory_stats.show(ui, |_| String::new());
+                });
+                return;
+            }
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("RAM Usage:");
+            self.alerts.tint(ui, "memory.used");
+            ui.label(
+                format!("{:.1}/{:.1} GB ({}%)",
+                    memory_stats.used_memory,
+                    memory_stats.total_memory,
+                    memory_stats.used_memory_percentage
+                )
+            );
+            self.history.show(ui, "memory.used");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Available:");
+            ui.label(format!("{:.1} GB", memory_stats.available_memory));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Page Cache:");
+            memory_stats.page_cache.show(ui, |page_cache| format!("{:.1} GB", page_cache));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Swap Usage:");
+            ui.label(format!("{:.1}/{:.1} GB", memory_stats.used_swap, memory_stats.total_swap));
+            self.history.show(ui, "memory.swap");
+        });
+    }
+
+    fn show_processes(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let header = ui.label("Processes");
+            if let Some(user) = &self.config.processes.user {
+                header.on_hover_text(format!("Only processes of {}", user));
+            }
+            ui.selectable_value(&mut self.process_sort, ProcessSort::Cpu, "CPU");
+            ui.selectable_value(&mut self.process_sort, ProcessSort::Memory, "RSS");
+        });
+
+        // The snapshot holds the top processes by both orders, so switching needs no rescan.
+        let mut top = self.snapshot.processes.clone();
+        processes::sort(&mut top, self.process_sort);
+
+        egui::Grid::new("process_grid")
+            .num_columns(4)
+            .show(ui, |ui| {
+                ui.weak("PID");
+                ui.weak("Name");
+                ui.weak("CPU");
+                ui.weak("RSS");
+                ui.end_row();
+
+                for process in top.iter().take(self.config.processes.count) {
+                    ui.label(process.pid.to_string());
+                    ui.label(&process.name).on_hover_text(process.user.as_deref().unwrap_or("?"));
+                    ui.label(format!("{:.1}%", process.cpu_usage));
+                    ui.label(format!("{:.0} MB", process.memory_megabytes));
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_alerts(&mut self, ui: &mut egui::Ui) {
+        let count = self.alerts.log().count();
+        if count == 0 {
+            return;
+        }
+
+        egui::CollapsingHeader::new(format!("Alerts ({})", count)).show(ui, |ui| {
+            for event in self.alerts.log() {
+                let age = event.time.elapsed().unwrap_or_default().as_secs();
+                let age = match age {
+                    0..60 => format!("{}s", age),
+                    60..3600 => format!("{}m", age / 60),
+                    _ => format!("{}h", age / 3600),
+                };
+                let text = format!("{:>3} {}", age, event.describe(&self.config.units));
+                match event.level.color() {
+                    Some(color) => ui.colored_label(color, text),
+                    None => ui.label(text),
+                };
+            }
+        });
+    }
+
+    fn show_self_stats(&mut self, ui: &mut egui::Ui) {
+        let self_stats = &self.snapshot.self_stats;
+        ui.weak(format!("Self: {:.1}% CPU | {:.0} MB | {} ms",
+            self_stats.cpu_usage,
+            self_stats.memory_megabytes,
+            self_stats.sample_time.as_millis()
+        ));
+    }
+}
+
+fn show_gpu_processes(ui: &mut egui::Ui, gpu_stats: &GpuStats) {
+    let processes = match &gpu_stats.processes {
+        Metric::Value(processes) if processes.is_empty() => return,
+        Metric::Value(processes) => processes,
+        Metric::Unavailable(_) => {
+            ui.horizontal(|ui| {
+                ui.label("Processes:");
+                gpu_stats.processes.show(ui, |_| String::new());
+            });
+            return;
+        }
+    };
+
+    egui::CollapsingHeader::new(format!("Processes ({})", processes.len()))
+        .id_salt(format!("{}.processes", gpu_stats.uuid))
+        .show(ui, |ui| {
+            egui::Grid::new(format!("{}.process_grid", gpu_stats.uuid))
+                .num_columns(4)
+                .show(ui, |ui| {
+                    ui.weak("PID");
+                    ui.weak("Name");
+                    ui.weak("VRAM");
+                    ui.weak("SM");
+                    ui.end_row();
+
+                    for process in processes.iter().take(GPU_PROCESS_ROWS) {
+                        ui.label(process.pid.to_string());
+                        ui.label(if process.name.is_empty() { "?" } else { &process.name });
+                        process.used_memory_megabytes.show(ui, |megabytes| format!("{:.0} MB", megabytes));
+                        process.sm_utilization.show(ui, |utilization| format!("{}%", utilization));
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn show_throttle_badge(ui: &mut egui::Ui, reason: ThrottleReason) {
+    let fill = match reason {
+        ThrottleReason::ThermalSlowdown | ThrottleReason::HwSlowdown => Color32::from_rgb(220, 60, 60),
+        ThrottleReason::PowerCap => Color32::from_rgb(230, 180, 40),
+        ThrottleReason::SyncBoost | ThrottleReason::Idle => Color32::GRAY,
+    };
+    egui::Frame::none()
+        .fill(fill)
+        .rounding(Rounding::same(3.0))
+        .inner_margin(egui::Margin::symmetric(4.0, 0.0))
+        .show(ui, |ui| {
+            ui.label(egui::RichText::new(reason.label()).small().color(Color32::BLACK));
+        })
+        .response
+        .on_hover_text(reason.description());
+}
+
+impl eframe::App for StatsApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        self.reload_config(ctx);
+
+        let (visuals, background) = match self.config.theme.mode {
+            Theme::Dark => (Visuals::dark(), Color32::from_black_alpha(self.config.theme.background_alpha)),
+            Theme::Light => (Visuals::light(), Color32::from_white_alpha(self.config.theme.background_alpha)),
+        };
+        ctx.set_visuals(visuals);
+
+        if self.visible {
+            self.fps_counter.update();
+        }
+
+        for action in self.hotkeys.pressed() {
+            self.handle_hotkey(ctx, action);
+        }
+
+        while let Ok(command) = self.remote_commands.try_recv() {
+            self.visible = match command {
+                ipc::Command::Toggle => !self.visible,
+                ipc::Command::Show => true,
+                ipc::Command::Hide => false,
+            };
+        }
+
+        self.refresh_stats();
+
+        if !self.visible {
+            ctx.request_repaint_after(Duration::from_secs(1));
+            return;
+        }
+
+        let panel_response = egui::CentralPanel::default()
+            .frame(egui::Frame::none()
+                .fill(background)
+                .rounding(Rounding::same(self.config.theme.rounding)))
+            .show(ctx, |ui| {
+                ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+
+                if let Some(settings) = self.settings.as_mut() {
+                    match settings.show(ui) {
+                        SettingsAction::Save => self.save_settings(ctx),
+                        SettingsAction::Cancel => self.settings = None,
+                        SettingsAction::None => {}
+                    }
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Performance Overlay");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(format!("{} FPS", self.fps_counter.current_fps));
+                        if let Some(recorder) = &self.recorder {
+                            ui.colored_label(Color32::from_rgb(220, 60, 60), "REC").on_hover_text(recorder.path().display().to_string());
+                        }
+                    });
+                });
+
+                if let Some(config_error) = &self.config_error {
+                    ui.colored_label(Color32::from_rgb(220, 60, 60), "Config not reloaded").on_hover_text(config_error);
+                }
+
+                if !self.hotkeys.conflicts().is_empty() {
+                    ui.colored_label(Color32::from_rgb(220, 60, 60), "Hotkey conflict").on_hover_text(self.hotkeys.conflicts().join("\n"));
+                }
+
+                if let Some((status, shown)) = &self.status {
+                    if shown.elapsed() < STATUS_DURATION {
+                        ui.weak(status);
+                    }
+                }
+
+                for panel in self.panels().to_vec() {
+                    match panel {
+                        Panel::Gpu | Panel::Cpu | Panel::Memory | Panel::Processes | Panel::Alerts => {
+                            ui.separator();
+                        }
+                        Panel::SelfStats => ui.add_space(4.0),
+                        _ => {}
+                    }
+                    self.show_panel(ui, panel);
+                }
+
+                ui.add_space(4.0);
+                if let Some(hotkey) = self.config.hotkey_label(HotkeyAction::ToggleOverlay) {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                        ui.label(format!("[{}] Toggle Overlay", hotkey));
+                    });
+                }
+            });
+
+        if self.settings.is_none() && panel_response.response.interact(egui::Sense::click()).secondary_clicked() {
+            self.open_settings();
+        }
+
+        if self.visible {
+            ctx.request_repaint_after(Duration::from_millis(16));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: system_stats_monitor [OPTIONS]
+
+Options:
+  --headless          Print stats to stdout instead of opening the overlay
+  --record            Record every sample to a CSV session from the start
+  --interval <MS>     Time between samples in headless mode [default: refresh_interval_ms from the config]
+  --count <N>         Exit after N samples in headless mode [default: run until interrupted]
+  --format <FORMAT>   Headless output: table, json (one pretty-printed document per sample)
+                      or ndjson (one line per sample) [default: table]
+  -h, --help          Print this help";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+    Ndjson,
+}
+
+/// Command line options. Anything not given here comes from the config file.
+#[derive(Default)]
+pub struct Options {
+    pub headless: bool,
+    pub record: bool,
+    pub interval: Option<Duration>,
+    pub count: Option<u64>,
+    pub format: Format,
+    pub help: bool,
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--record" => options.record = true,
+                "--interval" => {
+                    let interval = parse_value(&arg, args.next())?;
+                    if interval < 100 {
+                        return Err(format!("{} must be at least 100 ms, got {}", arg, interval));
+                    }
+                    options.interval = Some(Duration::from_millis(interval));
+                }
+                "--count" => {
+                    let count = parse_value(&arg, args.next())?;
+                    if count == 0 {
+                        return Err(format!("{} must be at least 1", arg));
+                    }
+                    options.count = Some(count);
+                }
+                "--format" => {
+                    options.format = match args.next().as_deref() {
+                        Some("table") => Format::Table,
+                        Some("json") => Format::Json,
+                        Some("ndjson") => Format::Ndjson,
+                        Some(format) => return Err(format!("{} expects table, json or ndjson, got \"{}\"", arg, format)),
+                        None => return Err(format!("{} needs a value", arg)),
+                    };
+                }
+                "-h" | "--help" => options.help = true,
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        if !options.headless && (options.interval.is_some() || options.count.is_some() || options.format != Format::Table) {
+            return Err(String::from("--interval, --count and --format only apply with --headless"));
+        }
+        Ok(options)
+    }
+}
+
+fn parse_value(option: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", option))?;
+    value.parse().map_err(|_| format!("{} expects a number, got \"{}\"", option, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_open_the_overlay() {
+        let options = parse(&[]).unwrap();
+        assert!(!options.headless && !options.record && !options.help);
+        assert_eq!(options.interval, None);
+        assert_eq!(options.count, None);
+    }
+
+    #[test]
+    fn parses_headless_options() {
+        let options = parse(&["--headless", "--interval", "500", "--count", "3", "--record"]).unwrap();
+        assert!(options.headless && options.record);
+        assert_eq!(options.interval, Some(Duration::from_millis(500)));
+        assert_eq!(options.count, Some(3));
+        assert!(parse(&["--help"]).unwrap().help);
+        assert!(parse(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert_eq!(parse(&["--headless", "--interval"]).err().unwrap(), "--interval needs a value");
+        assert_eq!(parse(&["--headless", "--interval", "fast"]).err().unwrap(), "--interval expects a number, got \"fast\"");
+        assert_eq!(parse(&["--headless", "--interval", "99"]).err().unwrap(), "--interval must be at least 100 ms, got 99");
+        assert_eq!(parse(&["--headless", "--count", "0"]).err().unwrap(), "--count must be at least 1");
+        assert_eq!(parse(&["--headless", "--count", "-1"]).err().unwrap(), "--count expects a number, got \"-1\"");
+        assert_eq!(parse(&["--verbose"]).err().unwrap(), "unknown option --verbose");
+    }
+
+    #[test]
+    fn parses_output_formats() {
+        assert!(parse(&["--headless"]).unwrap().format == Format::Table);
+        assert!(parse(&["--headless", "--format", "table"]).unwrap().format == Format::Table);
+        assert!(parse(&["--headless", "--format", "json"]).unwrap().format == Format::Json);
+        assert!(parse(&["--headless", "--format", "ndjson"]).unwrap().format == Format::Ndjson);
+        assert_eq!(parse(&["--headless", "--format", "csv"]).err().unwrap(), "--format expects table, json or ndjson, got \"csv\"");
+        assert_eq!(parse(&["--headless", "--format"]).err().unwrap(), "--format needs a value");
+    }
+
+    #[test]
+    fn headless_options_need_headless() {
+        assert_eq!(parse(&["--count", "1"]).err().unwrap(), "--interval, --count and --format only apply with --headless");
+        assert!(parse(&["--format", "json"]).is_err());
+        assert!(parse(&["--interval", "500"]).is_err());
+        assert!(parse(&["--record"]).is_ok());
+    }
+}
@@ -0,0 +1,429 @@
+use serde::{Serialize, Serializer};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Pid, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System, Users};
+use crate::config::{Config, Panel, ProcessesConfig};
+use crate::cpu::{CpuMonitor, CpuStats};
+use crate::gpu::{GpuBackend, GpuStats};
+use crate::memory::{self, MemoryStats};
+use crate::metric::Metric;
+use crate::processes::{self, ProcessStats};
+
+/// The monitor's own footprint, so the cost of sampling stays visible.
+#[derive(Default, Serialize)]
+pub struct SelfStats {
+    /// Percent of one core, like `top`.
+    pub cpu_usage: f32,
+    pub memory_megabytes: f32,
+    /// Wall time of the last sampling pass.
+    #[serde(rename = "sample_time_ms", serialize_with = "serialize_millis")]
+    pub sample_time: Duration,
+}
+
+/// Everything sampled in one collector pass. `sequence` increases by one per pass.
+#[derive(Serialize)]
+pub struct Snapshot {
+    pub sequence: u64,
+    /// When the pass started.
+    #[serde(rename = "timestamp_ms", serialize_with = "serialize_unix_millis")]
+    pub timestamp: SystemTime,
+    #[serde(rename = "gpus")]
+    pub gpu_stats: Metric<Vec<GpuStats>>,
+    #[serde(rename = "cpu")]
+    pub cpu_stats: CpuStats,
+    #[serde(rename = "memory")]
+    pub memory_stats: Metric<MemoryStats>,
+    /// Top processes by CPU and by RSS; empty unless the processes panel is shown.
+    pub processes: Vec<ProcessStats>,
+    #[serde(rename = "monitor")]
+    pub self_stats: SelfStats,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            sequence: 0,
+            timestamp: SystemTime::UNIX_EPOCH,
+            gpu_stats: Metric::Unavailable(String::from("Not sampled yet")),
+            cpu_stats: CpuStats::default(),
+            memory_stats: Metric::Unavailable(String::from("Not sampled yet")),
+            processes: Vec::new(),
+            self_stats: SelfStats::default(),
+        }
+    }
+}
+
+/// The most recent snapshot, shared between the thread that samples and everything that reads it.
+#[derive(Clone)]
+pub struct LatestSnapshot(Arc<RwLock<Arc<Snapshot>>>);
+
+impl LatestSnapshot {
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(Snapshot::default()))))
+    }
+
+    pub fn get(&self) -> Arc<Snapshot> {
+        Arc::clone(&self.0.read().unwrap())
+    }
+
+    pub fn set(&self, snapshot: Snapshot) {
+        *self.0.write().unwrap() = Arc::new(snapshot);
+    }
+}
+
+/// Samples on a dedicated thread so slow sensor reads never stall the UI, which only reads the latest snapshot.
+/// The thread exits once the `Collector` is dropped.
+pub struct Collector {
+    latest: LatestSnapshot,
+    config_updates: Sender<(Config, Scope)>,
+}
+
+impl Collector {
+    pub fn spawn(gpu: Box<dyn GpuBackend>, config: &Config, scope: Scope) -> Self {
+        let mut interval = config.refresh_interval();
+        let mut sampler = Sampler::new(gpu, config, scope);
+        let latest = LatestSnapshot::new();
+        let (config_updates, config_receiver) = mpsc::channel::<(Config, Scope)>();
+
+        {
+            let latest = latest.clone();
+            thread::Builder::new()
+                .name(String::from("stats-collector"))
+                .spawn(move || loop {
+                    latest.set(sampler.sample());
+
+                    match config_receiver.recv_timeout(interval) {
+                        Ok((config, scope)) => {
+                            interval = config.refresh_interval();
+                            sampler.apply(config, scope);
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                })
+                .expect("Failed to spawn collector thread");
+        }
+
+        Self { latest, config_updates }
+    }
+
+    /// Applies a reloaded config and what to sample from the next pass on; the GPU backend is kept as is.
+    pub fn apply(&self, config: &Config, scope: Scope) {
+        // The thread only stops once we are dropped, so the receiver is still alive.
+        let _ = self.config_updates.send((config.clone(), scope));
+    }
+
+    pub fn latest(&self) -> Arc<Snapshot> {
+        self.latest.get()
+    }
+
+    /// A handle that keeps following the collector's snapshots.
+    pub fn shared(&self) -> LatestSnapshot {
+        self.latest.clone()
+    }
+}
+
+/// Owns the sources and takes one snapshot per call, on whatever thread it lives on.
+pub struct Sampler {
+    gpu: Box<dyn GpuBackend>,
+    cpu: CpuMonitor,
+    system: System,
+    scope: Scope,
+    processes: ProcessesConfig,
+    users: Users,
+    own_pid: Option<Pid>,
+    sequence: u64,
+}
+
+impl Sampler {
+    pub fn new(gpu: Box<dyn GpuBackend>, config: &Config, scope: Scope) -> Self {
+        Self {
+            gpu,
+            cpu: CpuMonitor::new(config.cpu.temperature_sensor.clone(), config.cpu.fan.clone()),
+            system: System::new_with_specifics(scope.refresh_kind()),
+            scope,
+            processes: config.processes.clone(),
+            users: Users::new_with_refreshed_list(),
+            own_pid: sysinfo::get_current_pid().ok(),
+            sequence: 0,
+        }
+    }
+
+    pub fn sample(&mut self) -> Snapshot {
+        self.sequence += 1;
+        let started = Instant::now();
+        let timestamp = SystemTime::now();
+        let (gpu_stats, cpu_stats, memory_stats) = statscheck(&mut self.system, self.gpu.as_mut(), &mut self.cpu, self.scope);
+        let processes = match self.scope.processes {
+            true => processes::sample(&mut self.system, &self.users, &self.processes),
+            false => Vec::new(),
+        };
+        let self_stats = self_stats(&mut self.system, self.own_pid, self.scope.processes, started.elapsed());
+
+        Snapshot {
+            sequence: self.sequence,
+            timestamp,
+            gpu_stats,
+            cpu_stats,
+            memory_stats,
+            processes,
+            self_stats,
+        }
+    }
+
+    fn apply(&mut self, config: Config, scope: Scope) {
+        self.scope = scope;
+        self.processes = config.processes;
+        self.cpu = CpuMonitor::new(config.cpu.temperature_sensor, config.cpu.fan);
+    }
+}
+
+/// Which sources a pass reads. Temperatures and fans are read every pass; whatever else is left out
+/// shows up as unavailable in the snapshot rather than as zeros or stale values.
+#[derive(Clone, Copy)]
+pub struct Scope {
+    gpu: bool,
+    cpu: bool,
+    memory: bool,
+    processes: bool,
+}
+
+impl Scope {
+    /// What the enabled panels show, for an overlay that is the only reader.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            gpu: config.shows(Panel::Gpu),
+            cpu: config.shows(Panel::Cpu) || config.shows(Panel::CpuCores),
+            memory: config.shows(Panel::Memory),
+            processes: config.shows(Panel::Processes),
+        }
+    }
+
+    /// Everything, for readers that aren't tied to the panels: headless output, the exporter and recordings.
+    /// The process list stays opt-in through its panel.
+    pub fn full(config: &Config) -> Self {
+        Self {
+            gpu: true,
+            cpu: true,
+            memory: true,
+            processes: config.shows(Panel::Processes),
+        }
+    }
+
+    /// Only what the enabled panels show. Processes are deliberately left out;
+    /// rescanning all of them dominated the cost of a pass, so only the processes panel does that.
+    fn refresh_kind(self) -> RefreshKind {
+        let mut refresh_kind = RefreshKind::nothing();
+        if self.cpu {
+            refresh_kind = refresh_kind.with_cpu(CpuRefreshKind::nothing().with_cpu_usage().with_frequency());
+        }
+        if self.memory {
+            refresh_kind = refresh_kind.with_memory(MemoryRefreshKind::everything());
+        }
+        refresh_kind
+    }
+}
+
+fn statscheck(system: &mut System, gpu: &mut dyn GpuBackend, cpu: &mut CpuMonitor, scope: Scope) -> (Metric<Vec<GpuStats>>, CpuStats, Metric<MemoryStats>) {
+    let mut gpu_stats = if scope.gpu {
+        gpu.sample().into()
+    } else {
+        Metric::Unavailable(String::from("GPU panel disabled"))
+    };
+    if let Metric::Value(all_gpu_stats) = &mut gpu_stats {
+        name_gpu_processes(system, all_gpu_stats);
+    }
+
+    system.refresh_specifics(scope.refresh_kind());
+
+    let cpu_stats = cpu.sample(system, scope.cpu);
+    let memory_stats = match scope.memory {
+        true => Metric::Value(memory::sample(system)),
+        false => Metric::Unavailable(String::from("Memory panel disabled")),
+    };
+
+    (gpu_stats, cpu_stats, memory_stats)
+}
+
+/// Looks up the names of the processes using a GPU, refreshing just those processes.
+fn name_gpu_processes(system: &mut System, all_gpu_stats: &mut [GpuStats]) {
+    let processes = all_gpu_stats
+        .iter_mut()
+        .filter_map(|gpu_stats| match &mut gpu_stats.processes {
+            Metric::Value(processes) => Some(processes),
+            Metric::Unavailable(_) => None,
+        })
+        .flatten();
+    let mut processes: Vec<_> = processes.collect();
+    if processes.is_empty() {
+        return;
+    }
+
+    // A process using several GPUs is listed once per GPU, and sysinfo drops pids it is given twice.
+    let mut pids: Vec<Pid> = processes.iter().map(|process| Pid::from_u32(process.pid)).collect();
+    pids.sort();
+    pids.dedup();
+    system.refresh_processes_specifics(ProcessesToUpdate::Some(&pids), true, ProcessRefreshKind::nothing());
+
+    for process in processes.iter_mut() {
+        if let Some(found) = system.process(Pid::from_u32(process.pid)) {
+            process.name = found.name().to_string_lossy().into_owned();
+        }
+    }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+fn serialize_unix_millis<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_millis(&time.duration_since(UNIX_EPOCH).unwrap_or_default(), serializer)
+}
+
+/// Reads our own usage, reusing the full process refresh when the processes panel already did one.
+fn self_stats(system: &mut System, own_pid: Option<Pid>, processes_refreshed: bool, sample_time: Duration) -> SelfStats {
+    let Some(own_pid) = own_pid else {
+        return SelfStats { sample_time, ..SelfStats::default() };
+    };
+
+    // Refreshing twice in one pass would measure CPU usage over the few microseconds in between.
+    if !processes_refreshed {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[own_pid]),
+            false,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+    }
+
+    match system.process(own_pid) {
+        Some(process) => SelfStats {
+            cpu_usage: process.cpu_usage(),
+            memory_megabytes: process.memory() as f32 / 1024.0 / 1024.0,
+            sample_time,
+        },
+        None => SelfStats { sample_time, ..SelfStats::default() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::FakeGpuBackend;
+    use nvml_wrapper::error::NvmlError;
+
+    fn config(panels: &[Panel]) -> Config {
+        Config { panels: panels.to_vec(), ..Config::default() }
+    }
+
+    fn sampler(gpu: Box<dyn GpuBackend>, config: &Config) -> Sampler {
+        Sampler::new(gpu, config, Scope::from_config(config))
+    }
+
+    /// A demo device whose readings all failed, like `NvmlBackend` reports a card that fell off the bus.
+    fn unavailable_device(index: u32) -> GpuStats {
+        fn unavailable<T>() -> Metric<T> {
+            Metric::Unavailable(NvmlError::GpuLost.to_string())
+        }
+        let mut gpu_stats = FakeGpuBackend::demo().sample().unwrap().remove(0);
+        gpu_stats.index = index;
+        gpu_stats.pci_bus_id = unavailable();
+        gpu_stats.utilization = unavailable();
+        gpu_stats.used_memory = unavailable();
+        gpu_stats.used_memory_percentage = unavailable();
+        gpu_stats.temperature = unavailable();
+        gpu_stats.power_usage = unavailable();
+        gpu_stats.processes = unavailable();
+        gpu_stats
+    }
+
+    struct FailingGpuBackend;
+
+    impl GpuBackend for FailingGpuBackend {
+        fn sample(&mut self) -> Result<Vec<GpuStats>, NvmlError> {
+            Err(NvmlError::DriverNotLoaded)
+        }
+    }
+
+    #[test]
+    fn replays_the_fake_script_one_frame_per_pass() {
+        let config = config(&[Panel::Gpu, Panel::Cpu, Panel::Memory]);
+        let mut sampler = sampler(Box::new(FakeGpuBackend::demo()), &config);
+
+        let first = sampler.sample();
+        let second = sampler.sample();
+
+        assert_eq!((first.sequence, second.sequence), (1, 2));
+        let temperatures = |snapshot: &Snapshot| -> Vec<u32> {
+            snapshot.gpu_stats.value().unwrap().iter().map(|gpu_stats| *gpu_stats.temperature.value().unwrap()).collect()
+        };
+        assert_eq!(temperatures(&first), vec![42, 78]);
+        assert_eq!(temperatures(&second), vec![61, 66]);
+        assert!(first.memory_stats.value().is_some());
+    }
+
+    #[test]
+    fn names_gpu_processes() {
+        let config = config(&[Panel::Gpu]);
+        let mut sampler = sampler(Box::new(FakeGpuBackend::demo()), &config);
+
+        let snapshot = sampler.sample();
+        // The demo attributes its processes to the test binary itself.
+        let process = &snapshot.gpu_stats.value().unwrap()[0].processes.value().unwrap()[0];
+        assert_eq!(process.pid, std::process::id());
+        assert!(!process.name.is_empty());
+    }
+
+    #[test]
+    fn an_unavailable_device_only_blanks_its_own_entry() {
+        let healthy = FakeGpuBackend::demo().sample().unwrap().remove(0);
+        let backend = FakeGpuBackend::new(vec![vec![healthy, unavailable_device(1)]]);
+        let config = config(&[Panel::Gpu]);
+        let mut sampler = sampler(Box::new(backend), &config);
+
+        let snapshot = sampler.sample();
+
+        let all_gpu_stats = snapshot.gpu_stats.value().unwrap();
+        assert_eq!(all_gpu_stats.len(), 2);
+        assert_eq!(all_gpu_stats[0].temperature.value(), Some(&42));
+        assert!(all_gpu_stats[1].temperature.value().is_none());
+        assert!(all_gpu_stats[1].processes.value().is_none());
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["gpus"][0]["temperature"], 42);
+        assert!(json["gpus"][1]["temperature"].is_null());
+        assert!(json["gpus"][1]["pci_bus_id"].is_null());
+    }
+
+    #[test]
+    fn a_failing_backend_makes_every_gpu_unavailable() {
+        let config = config(&[Panel::Gpu, Panel::Cpu]);
+        let mut sampler = sampler(Box::new(FailingGpuBackend), &config);
+
+        let snapshot = sampler.sample();
+
+        match &snapshot.gpu_stats {
+            Metric::Unavailable(reason) => assert_eq!(reason, &NvmlError::DriverNotLoaded.to_string()),
+            Metric::Value(_) => panic!("expected the GPUs to be unavailable"),
+        }
+        assert!(snapshot.cpu_stats.total_usage.value().is_some());
+    }
+
+    #[test]
+    fn skips_what_the_panels_do_not_show() {
+        let config = config(&[Panel::Cpu]);
+        let mut sampler = sampler(Box::new(FakeGpuBackend::demo()), &config);
+
+        let snapshot = sampler.sample();
+
+        assert!(snapshot.gpu_stats.value().is_none());
+        assert!(snapshot.memory_stats.value().is_none());
+        assert!(snapshot.processes.is_empty());
+
+        let mut sampler = Sampler::new(Box::new(FakeGpuBackend::demo()), &config, Scope::full(&config));
+        let snapshot = sampler.sample();
+        assert!(snapshot.gpu_stats.value().is_some());
+        assert!(snapshot.memory_stats.value().is_some());
+    }
+}
@@ -0,0 +1,786 @@
+use global_hotkey::hotkey::HotKey;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+const CONFIG_DIR: &str = "system-stats-monitor";
+const CONFIG_FILE: &str = "config.toml";
+const MIN_REFRESH_INTERVAL_MS: u64 = 100;
+/// Every series keeps this much, so the limit bounds the memory the history takes.
+pub const MAX_HISTORY_SECONDS: u64 = 3600;
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sections of the overlay, drawn in the order they are listed in `panels`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Panel {
+    Gpu,
+    Cpu,
+    CpuCores,
+    CpuTemperatures,
+    Fans,
+    Memory,
+    Processes,
+    Alerts,
+    SelfStats,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 9] = [
+        Panel::Gpu,
+        Panel::Cpu,
+        Panel::CpuCores,
+        Panel::CpuTemperatures,
+        Panel::Fans,
+        Panel::Memory,
+        Panel::Processes,
+        Panel::Alerts,
+        Panel::SelfStats,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Panel::Gpu => "GPU",
+            Panel::Cpu => "CPU",
+            Panel::CpuCores => "CPU cores",
+            Panel::CpuTemperatures => "Core temperatures",
+            Panel::Fans => "Fans",
+            Panel::Memory => "Memory",
+            Panel::Processes => "Processes",
+            Panel::Alerts => "Alerts",
+            Panel::SelfStats => "Self stats",
+        }
+    }
+}
+
+/// Things a global hotkey can do.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    ToggleOverlay,
+    Settings,
+    CycleLayout,
+    ToggleLogging,
+    ResetHistory,
+    ToggleClickThrough,
+    CopySnapshot,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 7] = [
+        HotkeyAction::ToggleOverlay,
+        HotkeyAction::Settings,
+        HotkeyAction::CycleLayout,
+        HotkeyAction::ToggleLogging,
+        HotkeyAction::ResetHistory,
+        HotkeyAction::ToggleClickThrough,
+        HotkeyAction::CopySnapshot,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleOverlay => "Toggle Overlay",
+            HotkeyAction::Settings => "Settings",
+            HotkeyAction::CycleLayout => "Cycle Layout",
+            HotkeyAction::ToggleLogging => "Start/Stop Logging",
+            HotkeyAction::ResetHistory => "Reset Min/Max",
+            HotkeyAction::ToggleClickThrough => "Click-Through",
+            HotkeyAction::CopySnapshot => "Copy Snapshot",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleOverlay => "toggle_overlay",
+            HotkeyAction::Settings => "settings",
+            HotkeyAction::CycleLayout => "cycle_layout",
+            HotkeyAction::ToggleLogging => "toggle_logging",
+            HotkeyAction::ResetHistory => "reset_history",
+            HotkeyAction::ToggleClickThrough => "toggle_click_through",
+            HotkeyAction::CopySnapshot => "copy_snapshot",
+        }
+    }
+}
+
+/// A named panel list that the cycle-layout hotkey switches to.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutPreset {
+    pub name: String,
+    pub panels: Vec<Panel>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    Cpu,
+    Memory,
+}
+
+/// Readings that can raise alerts. GPU metrics are checked per device.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    GpuTemperature,
+    GpuMemory,
+    CpuUsage,
+    CpuTemperature,
+    MemoryUsage,
+}
+
+impl AlertMetric {
+    pub fn is_temperature(self) -> bool {
+        matches!(self, AlertMetric::GpuTemperature | AlertMetric::CpuTemperature)
+    }
+}
+
+/// Levels for one metric, in its own unit (°C for temperatures, whatever the display unit, percent otherwise).
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Threshold {
+    pub warning: f32,
+    pub critical: f32,
+    /// How far a value has to fall back below a level before it clears, so readings hovering
+    /// around a threshold don't flicker.
+    #[serde(default)]
+    pub hysteresis: f32,
+    /// How long a level has to be exceeded before it is raised.
+    #[serde(default)]
+    pub sustain_seconds: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub fn format(self, celsius: f32, precision: usize) -> String {
+        match self {
+            TemperatureUnit::Celsius => format!("{:.*}°C", precision, celsius),
+            TemperatureUnit::Fahrenheit => format!("{:.*}°F", precision, celsius * 9.0 / 5.0 + 32.0),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub refresh_interval_ms: u64,
+    pub history_seconds: u64,
+    pub panels: Vec<Panel>,
+    pub layouts: Vec<LayoutPreset>,
+    pub units: UnitsConfig,
+    /// Key combination per action, e.g. `toggle_overlay = "Alt+T"`. Actions left out keep their
+    /// default binding; an empty string unbinds one.
+    #[serde(deserialize_with = "merge_default_hotkeys")]
+    pub hotkeys: BTreeMap<HotkeyAction, String>,
+    pub window: WindowConfig,
+    pub theme: ThemeConfig,
+    pub gpu: GpuConfig,
+    pub cpu: CpuConfig,
+    pub recording: RecordingConfig,
+    pub exporter: ExporterConfig,
+    pub ipc: IpcConfig,
+    /// Thresholds per metric. Metrics left out keep their defaults.
+    #[serde(deserialize_with = "merge_default_alerts")]
+    pub alerts: BTreeMap<AlertMetric, Threshold>,
+    pub notifications: NotificationConfig,
+    pub processes: ProcessesConfig,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UnitsConfig {
+    pub temperature: TemperatureUnit,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: f32,
+    pub height: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub mode: Theme,
+    /// Opacity of the overlay background, 0 (transparent) to 255 (opaque).
+    pub background_alpha: u8,
+    pub rounding: f32,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpuConfig {
+    /// `nvml`, `none` or `fake`; `STATS_GPU_BACKEND` takes precedence.
+    pub backend: Option<String>,
+    /// UUIDs of the GPUs to show at startup; empty shows all of them.
+    pub pinned: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuConfig {
+    /// Sensor label to use for the CPU temperature, e.g. `k10temp Tccd1`.
+    pub temperature_sensor: Option<String>,
+    /// Fan label to show as the CPU fan, e.g. `nct6798 fan2`.
+    pub fan: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordingConfig {
+    /// Where CSV sessions go; defaults to the data dir.
+    pub dir: Option<PathBuf>,
+    /// Start a new file once the current one reaches this size; 0 never rotates by size.
+    pub max_file_mb: u64,
+    /// Start a new file once the current one is this old; 0 never rotates by age.
+    pub max_file_minutes: u64,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExporterConfig {
+    /// Address to serve Prometheus metrics on, e.g. `127.0.0.1:9184`; unset disables the exporter.
+    /// Read at startup only.
+    pub listen: Option<SocketAddr>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpcConfig {
+    pub enabled: bool,
+    /// Socket path; defaults to `system-stats-monitor.sock` in the runtime dir. Read at startup only.
+    pub socket: Option<PathBuf>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessesConfig {
+    /// Rows in the processes panel.
+    pub count: usize,
+    /// Initial order; the panel can switch it.
+    pub sort: ProcessSort,
+    /// Only list processes of this user.
+    pub user: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    /// Read at startup only.
+    pub enabled: bool,
+    /// How long a reading has to stay critical before a notification is raised.
+    pub critical_seconds: u64,
+    /// Minimum time between notifications about the same reading.
+    pub min_interval_seconds: u64,
+    /// Follow up with a "resolved" notification once the reading recovers.
+    pub resolved: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval_ms: 1000,
+            history_seconds: 300,
+            // Listing processes means scanning all of them every pass, so that panel is opt-in.
+            panels: Panel::ALL.into_iter().filter(|panel| *panel != Panel::Processes).collect(),
+            layouts: vec![
+                LayoutPreset {
+                    name: String::from("compact"),
+                    panels: vec![Panel::Gpu, Panel::Cpu, Panel::Memory],
+                },
+                LayoutPreset {
+                    name: String::from("minimal"),
+                    panels: vec![Panel::Gpu, Panel::Cpu],
+                },
+            ],
+            units: UnitsConfig::default(),
+            hotkeys: default_hotkeys(),
+            window: WindowConfig::default(),
+            theme: ThemeConfig::default(),
+            gpu: GpuConfig::default(),
+            cpu: CpuConfig::default(),
+            recording: RecordingConfig::default(),
+            exporter: ExporterConfig::default(),
+            ipc: IpcConfig::default(),
+            alerts: default_alerts(),
+            notifications: NotificationConfig::default(),
+            processes: ProcessesConfig::default(),
+        }
+    }
+}
+
+fn default_hotkeys() -> BTreeMap<HotkeyAction, String> {
+    BTreeMap::from([
+        (HotkeyAction::ToggleOverlay, String::from("Alt+T")),
+        (HotkeyAction::Settings, String::from("")),
+        (HotkeyAction::CycleLayout, String::from("")),
+        (HotkeyAction::ToggleLogging, String::from("")),
+        (HotkeyAction::ResetHistory, String::from("")),
+        (HotkeyAction::ToggleClickThrough, String::from("")),
+        (HotkeyAction::CopySnapshot, String::from("")),
+    ])
+}
+
+fn merge_default_hotkeys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<HotkeyAction, String>, D::Error> {
+    let mut hotkeys = default_hotkeys();
+    hotkeys.extend(BTreeMap::<HotkeyAction, String>::deserialize(deserializer)?);
+    Ok(hotkeys)
+}
+
+fn default_alerts() -> BTreeMap<AlertMetric, Threshold> {
+    let threshold = |warning, critical, hysteresis, sustain_seconds| Threshold { warning, critical, hysteresis, sustain_seconds };
+    BTreeMap::from([
+        (AlertMetric::GpuTemperature, threshold(75.0, 83.0, 3.0, 0)),
+        (AlertMetric::GpuMemory, threshold(90.0, 95.0, 2.0, 0)),
+        (AlertMetric::CpuUsage, threshold(80.0, 90.0, 5.0, 10)),
+        (AlertMetric::CpuTemperature, threshold(80.0, 90.0, 3.0, 0)),
+        (AlertMetric::MemoryUsage, threshold(85.0, 95.0, 2.0, 0)),
+    ])
+}
+
+fn merge_default_alerts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<AlertMetric, Threshold>, D::Error> {
+    let mut alerts = default_alerts();
+    alerts.extend(BTreeMap::<AlertMetric, Threshold>::deserialize(deserializer)?);
+    Ok(alerts)
+}
+
+impl Default for UnitsConfig {
+    fn default() -> Self {
+        Self {
+            temperature: TemperatureUnit::Celsius,
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 300.0,
+            height: 400.0,
+            x: 1620.0,
+            y: 0.0,
+        }
+    }
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_file_mb: 100,
+            max_file_minutes: 60,
+        }
+    }
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket: None,
+        }
+    }
+}
+
+impl Default for ProcessesConfig {
+    fn default() -> Self {
+        Self {
+            count: 5,
+            sort: ProcessSort::Cpu,
+            user: None,
+        }
+    }
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            critical_seconds: 30,
+            min_interval_seconds: 600,
+            resolved: true,
+        }
+    }
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            mode: Theme::Dark,
+            background_alpha: 200,
+            rounding: 6.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Write(PathBuf, std::io::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "failed to parse {}: {}", path.display(), e),
+            ConfigError::Write(path, e) => write!(f, "failed to write {}: {}", path.display(), e),
+            ConfigError::Invalid(reason) => write!(f, "invalid config: {}", reason),
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/system-stats-monitor/config.toml` or the platform equivalent.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR).join(CONFIG_FILE))
+    }
+
+    /// Loads and validates the config at `path`; a missing file yields the defaults.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ConfigError::Read(path.to_path_buf(), e)),
+        };
+
+        let config: Self = toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates and writes the config to `path`, creating its directory if needed. Values are merged
+    /// into the existing file so its comments and layout survive; one that doesn't parse is replaced.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        self.validate()?;
+
+        let contents = toml::to_string_pretty(self).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        let existing = std::fs::read_to_string(path).ok().and_then(|existing| existing.parse::<DocumentMut>().ok());
+        let contents = match (existing, contents.parse::<DocumentMut>()) {
+            (Some(mut existing), Ok(saved)) => {
+                merge_table(existing.as_table_mut(), saved.as_table());
+                existing.to_string()
+            }
+            _ => contents,
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| ConfigError::Write(path.to_path_buf(), e))?;
+        }
+        std::fs::write(path, contents).map_err(|e| ConfigError::Write(path.to_path_buf(), e))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.refresh_interval_ms < MIN_REFRESH_INTERVAL_MS {
+            return Err(ConfigError::Invalid(format!(
+                "refresh_interval_ms must be at least {}, got {}",
+                MIN_REFRESH_INTERVAL_MS, self.refresh_interval_ms
+            )));
+        }
+
+        if self.history_seconds == 0 || self.history_seconds > MAX_HISTORY_SECONDS {
+            return Err(ConfigError::Invalid(format!(
+                "history_seconds must be between 1 and {}, got {}",
+                MAX_HISTORY_SECONDS, self.history_seconds
+            )));
+        }
+
+        validate_panels("panels", &self.panels)?;
+        for layout in &self.layouts {
+            if layout.name.is_empty() {
+                return Err(ConfigError::Invalid(String::from("layouts need a name")));
+            }
+            validate_panels(&format!("layout \"{}\"", layout.name), &layout.panels)?;
+        }
+
+        if self.processes.count == 0 {
+            return Err(ConfigError::Invalid(String::from("processes.count must be greater than 0")));
+        }
+
+        if self.window.width <= 0.0 || self.window.height <= 0.0 {
+            return Err(ConfigError::Invalid(format!(
+                "window size must be positive, got {}x{}",
+                self.window.width, self.window.height
+            )));
+        }
+
+        if let Some(backend) = &self.gpu.backend {
+            if !["nvml", "none", "fake"].contains(&backend.as_str()) {
+                return Err(ConfigError::Invalid(format!("gpu.backend must be nvml, none or fake, got \"{}\"", backend)));
+            }
+        }
+
+        for (metric, threshold) in &self.alerts {
+            if threshold.warning > threshold.critical || threshold.hysteresis < 0.0 {
+                return Err(ConfigError::Invalid(format!(
+                    "alerts.{:?}: warning must not exceed critical and hysteresis must not be negative",
+                    metric
+                )));
+            }
+        }
+
+        let bindings = self.hotkey_bindings()?;
+        for (i, (action, hotkey)) in bindings.iter().enumerate() {
+            if let Some((other, _)) = bindings[..i].iter().find(|(_, other)| other == hotkey) {
+                return Err(ConfigError::Invalid(format!(
+                    "hotkeys.{} and hotkeys.{} are both bound to \"{}\"",
+                    other.key(), action.key(), self.hotkeys[action]
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parsed key combinations of every bound action.
+    pub fn hotkey_bindings(&self) -> Result<Vec<(HotkeyAction, HotKey)>, ConfigError> {
+        self.hotkeys
+            .iter()
+            .filter(|(_, hotkey)| !hotkey.is_empty())
+            .map(|(action, hotkey)| Ok((*action, parse_hotkey(&format!("hotkeys.{}", action.key()), hotkey)?)))
+            .collect()
+    }
+
+    /// The key combination bound to `action`, as written in the config.
+    pub fn hotkey_label(&self, action: HotkeyAction) -> Option<&str> {
+        self.hotkeys.get(&action).map(String::as_str).filter(|hotkey| !hotkey.is_empty())
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_millis(self.refresh_interval_ms)
+    }
+
+    /// Number of samples needed to cover `history_seconds` at the configured refresh rate.
+    pub fn history_length(&self) -> usize {
+        (self.history_seconds.saturating_mul(1000) / self.refresh_interval_ms.max(1)).max(1) as usize
+    }
+
+    pub fn shows(&self, panel: Panel) -> bool {
+        self.panels.contains(&panel)
+    }
+}
+
+fn validate_panels(name: &str, panels: &[Panel]) -> Result<(), ConfigError> {
+    let mut seen = HashSet::new();
+    match panels.iter().find(|panel| !seen.insert(**panel)) {
+        Some(panel) => Err(ConfigError::Invalid(format!("{}: panel {:?} is listed more than once", name, panel))),
+        None => Ok(()),
+    }
+}
+
+/// Updates `existing` to hold exactly the keys and values of `saved`, leaving the comments and formatting
+/// of everything that didn't change in place.
+fn merge_table(existing: &mut Table, saved: &Table) {
+    existing.retain(|key, _| saved.contains_key(key));
+    for (key, item) in saved.iter() {
+        match (existing.get_mut(key), item) {
+            (Some(Item::Table(existing)), Item::Table(saved)) => merge_table(existing, saved),
+            (Some(Item::Value(existing)), Item::Value(saved)) => {
+                if !same_value(existing, saved) {
+                    let decor = existing.decor().clone();
+                    *existing = saved.clone();
+                    *existing.decor_mut() = decor;
+                }
+            }
+            _ => {
+                existing.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// Compares values regardless of how they are written (quotes, digit separators, line breaks, comments).
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Float(a), Value::Float(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b)),
+        (Value::InlineTable(a), Value::InlineTable(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| same_value(a, b)))
+        }
+        _ => false,
+    }
+}
+
+fn parse_hotkey(name: &str, hotkey: &str) -> Result<HotKey, ConfigError> {
+    hotkey
+        .parse()
+        .map_err(|e| ConfigError::Invalid(format!("{} \"{}\": {}", name, hotkey, e)))
+}
+
+/// Polls the config file's modification time and reloads it when it changes.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Returns the reloaded config if the file changed since the last call, at most once per second.
+    pub fn poll(&mut self) -> Option<Result<Config, ConfigError>> {
+        if self.last_check.elapsed() < WATCH_INTERVAL {
+            return None;
+        }
+        self.last_check = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(Config::load(&self.path))
+    }
+
+    /// Records the file as seen after we wrote it ourselves, so the write isn't picked up as an edit.
+    pub fn sync(&mut self) {
+        self.modified = modified_time(&self.path);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("system-stats-monitor-{}-{}.toml", std::process::id(), name))
+    }
+
+    fn parse(contents: &str) -> Result<Config, ConfigError> {
+        let config: Config = toml::from_str(contents).map_err(|e| ConfigError::Parse(PathBuf::from("config.toml"), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn error(contents: &str) -> String {
+        match parse(contents) {
+            Ok(_) => panic!("expected {:?} to be rejected", contents),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Config::default().validate().is_ok());
+        assert!(parse("").is_ok());
+        let missing = Config::load(&temp_path("missing")).unwrap();
+        assert_eq!(missing.refresh_interval_ms, Config::default().refresh_interval_ms);
+    }
+
+    #[test]
+    fn hotkeys_left_out_keep_their_defaults() {
+        let config = parse("[hotkeys]\ncycle_layout = \"Alt+L\"\n").unwrap();
+        assert_eq!(config.hotkey_label(HotkeyAction::ToggleOverlay), Some("Alt+T"));
+        assert_eq!(config.hotkey_label(HotkeyAction::CycleLayout), Some("Alt+L"));
+        assert_eq!(config.hotkey_label(HotkeyAction::Settings), None);
+
+        let config = parse("[hotkeys]\ntoggle_overlay = \"\"\n").unwrap();
+        assert_eq!(config.hotkey_label(HotkeyAction::ToggleOverlay), None);
+        assert!(config.hotkey_bindings().unwrap().is_empty());
+    }
+
+    #[test]
+    fn alerts_left_out_keep_their_defaults() {
+        let config = parse("[alerts.cpu_usage]\nwarning = 50.0\ncritical = 60.0\n").unwrap();
+        let cpu_usage = config.alerts[&AlertMetric::CpuUsage];
+        assert_eq!((cpu_usage.warning, cpu_usage.critical), (50.0, 60.0));
+        // A threshold given in the file replaces the default one as a whole.
+        assert_eq!((cpu_usage.hysteresis, cpu_usage.sustain_seconds), (0.0, 0));
+        assert_eq!(config.alerts[&AlertMetric::GpuTemperature].critical, 83.0);
+        assert_eq!(config.alerts.len(), default_alerts().len());
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        assert!(error("refresh_interval_ms = 50").contains("refresh_interval_ms must be at least 100, got 50"));
+        assert!(error("history_seconds = 0").contains("history_seconds must be between 1 and 3600, got 0"));
+        assert!(error("history_seconds = 3601").contains("got 3601"));
+        assert!(error("panels = [\"gpu\", \"cpu\", \"gpu\"]").contains("panels: panel Gpu is listed more than once"));
+        assert!(error("[[layouts]]\nname = \"\"\npanels = [\"gpu\"]").contains("layouts need a name"));
+        assert!(error("[[layouts]]\nname = \"busy\"\npanels = [\"cpu\", \"cpu\"]").contains("layout \"busy\": panel Cpu"));
+        assert!(error("[processes]\ncount = 0").contains("processes.count must be greater than 0"));
+        assert!(error("[window]\nwidth = 0.0").contains("window size must be positive"));
+        assert!(error("[gpu]\nbackend = \"cuda\"").contains("gpu.backend must be nvml, none or fake, got \"cuda\""));
+        assert!(error("[alerts.memory_usage]\nwarning = 95.0\ncritical = 90.0").contains("alerts.MemoryUsage"));
+        assert!(error("[alerts.memory_usage]\nwarning = 80.0\ncritical = 90.0\nhysteresis = -1.0").contains("alerts.MemoryUsage"));
+        assert!(error("[hotkeys]\ncycle_layout = \"Alt+T\"").contains("hotkeys.toggle_overlay and hotkeys.cycle_layout are both bound to \"Alt+T\""));
+        assert!(error("[hotkeys]\nsettings = \"Alt+Nope\"").contains("hotkeys.settings \"Alt+Nope\""));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(error("refresh_interval = 500").contains("failed to parse"));
+        assert!(error("[window]\nopacity = 0.5").contains("unknown field"));
+        assert!(error("[alerts.disk_usage]\nwarning = 1.0\ncritical = 2.0").contains("failed to parse"));
+    }
+
+    #[test]
+    fn save_keeps_comments_and_unchanged_formatting() {
+        let path = temp_path("save-comments");
+        let original = "# Tuned for the laptop\nrefresh_interval_ms = 1_000  # one second\npanels = [\"gpu\", 'cpu']\n\n[window]\n# Top right corner\nwidth = 300.0\n";
+        std::fs::write(&path, original).unwrap();
+
+        let mut config = Config::load(&path).unwrap();
+        config.window.width = 420.0;
+        config.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(saved.starts_with("# Tuned for the laptop\nrefresh_interval_ms = 1_000  # one second\npanels = [\"gpu\", 'cpu']\n"));
+        assert!(saved.contains("[window]\n# Top right corner\nwidth = 420.0\n"));
+        let reloaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.window.width, 420.0);
+        assert_eq!(reloaded.refresh_interval_ms, 1000);
+    }
+
+    #[test]
+    fn save_replaces_a_file_that_does_not_parse() {
+        let path = temp_path("save-unparsable");
+        std::fs::write(&path, "refresh_interval_ms = \n").unwrap();
+
+        Config::default().save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let reloaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.refresh_interval_ms, Config::default().refresh_interval_ms);
+    }
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+use sysinfo::{Components, System};
+use crate::metric::Metric;
+use crate::sensors::{self, FanReading, TemperatureReading};
+
+/// Package sensors tried in order when no override is configured, as `(chip, label)`.
+/// An empty label matches any channel of that chip.
+const PACKAGE_SENSORS: &[(&str, &str)] = &[
+    ("k10temp", "Tctl"),
+    ("k10temp", "Tdie"),
+    ("zenpower", "Tdie"),
+    ("coretemp", "Package id 0"),
+    ("x86_pkg_temp", ""),
+    ("cpu_thermal", ""),
+    ("acpitz", ""),
+];
+
+/// Usage at or above which a core is considered saturated.
+pub const SATURATED_CORE_USAGE: f32 = 95.0;
+
+#[derive(Clone, Serialize)]
+pub struct CoreStats {
+    pub usage: f32,
+    /// Current frequency in MHz.
+    pub frequency: u64,
+}
+
+#[derive(Serialize)]
+pub struct CpuStats {
+    pub total_usage: Metric<i8>,
+    /// One entry per logical core, in `System::cpus` order.
+    pub cores: Metric<Vec<CoreStats>>,
+    pub temperature: Metric<f32>,
+    /// Per-core (coretemp) or per-CCD (k10temp/zenpower) temperatures, labelled as reported.
+    pub core_temperatures: Vec<(String, f32)>,
+    /// RPM of the fan mapped as the CPU fan.
+    pub fan_speed: Metric<u32>,
+    /// RPM of every fan found, labelled as `chip label`.
+    pub fans: Vec<(String, u32)>,
+}
+
+impl Default for CpuStats {
+    fn default() -> Self {
+        Self {
+            total_usage: Metric::Unavailable(String::from("Not sampled yet")),
+            cores: Metric::Unavailable(String::from("Not sampled yet")),
+            temperature: Metric::Unavailable(String::from("Not sampled yet")),
+            core_temperatures: Vec::new(),
+            fan_speed: Metric::Unavailable(String::from("Not sampled yet")),
+            fans: Vec::new(),
+        }
+    }
+}
+
+pub struct CpuMonitor {
+    temperature_sensor: Option<String>,
+    fan: Option<String>,
+    components: Components,
+}
+
+impl CpuMonitor {
+    pub fn new(temperature_sensor: Option<String>, fan: Option<String>) -> Self {
+        Self {
+            temperature_sensor,
+            fan,
+            components: Components::new(),
+        }
+    }
+
+    /// Sensors are read every time; usage and clocks only when `system` refreshed them (`usage_refreshed`).
+    pub fn sample(&mut self, system: &System, usage_refreshed: bool) -> CpuStats {
+        let readings = self.read_temperatures();
+        let fans = sensors::read_fans();
+
+        CpuStats {
+            total_usage: match usage_refreshed {
+                true => Metric::Value(system.global_cpu_usage() as i8),
+                false => Metric::Unavailable(String::from("CPU panels disabled")),
+            },
+            cores: match usage_refreshed {
+                true => Metric::Value(
+                    system
+                        .cpus()
+                        .iter()
+                        .map(|cpu| CoreStats {
+                            usage: cpu.cpu_usage(),
+                            frequency: cpu.frequency(),
+                        })
+                        .collect(),
+                ),
+                false => Metric::Unavailable(String::from("CPU panels disabled")),
+            },
+            temperature: self.package_temperature(&readings),
+            core_temperatures: readings
+                .iter()
+                .filter(|reading| is_core_sensor(reading))
+                .map(|reading| (reading.label.clone(), reading.celsius))
+                .collect(),
+            fan_speed: self.cpu_fan_speed(&fans),
+            fans: fans.iter().map(|fan| (fan.full_label(), fan.rpm)).collect(),
+        }
+    }
+
+    /// Reads sysfs directly; `sysinfo::Components` is only used where sysfs isn't available.
+    fn read_temperatures(&mut self) -> Vec<TemperatureReading> {
+        let readings = sensors::read_temperatures();
+        if !readings.is_empty() {
+            return readings;
+        }
+
+        self.components.refresh(true);
+        self.components
+            .iter()
+            .filter_map(|component| Some(component_reading(component.label(), component.temperature()?)))
+            .collect()
+    }
+
+    fn package_temperature(&self, readings: &[TemperatureReading]) -> Metric<f32> {
+        if let Some(sensor) = &self.temperature_sensor {
+            return match readings.iter().find(|reading| reading.full_label().eq_ignore_ascii_case(sensor)) {
+                Some(reading) => Metric::Value(reading.celsius),
+                None => Metric::Unavailable(format!("Sensor \"{sensor}\" not found")),
+            };
+        }
+
+        PACKAGE_SENSORS
+            .iter()
+            .find_map(|(chip, label)| {
+                readings
+                    .iter()
+                    .find(|reading| reading.chip == *chip && (label.is_empty() || reading.label == *label))
+            })
+            .map(|reading| Metric::Value(reading.celsius))
+            .unwrap_or_else(|| Metric::Unavailable(String::from("No CPU temperature sensor found")))
+    }
+
+    /// Uses the configured fan, or else the first fan whose label mentions the CPU.
+    fn cpu_fan_speed(&self, fans: &[FanReading]) -> Metric<u32> {
+        if let Some(fan) = &self.fan {
+            return match fans.iter().find(|reading| reading.full_label().eq_ignore_ascii_case(fan)) {
+                Some(reading) => Metric::Value(reading.rpm),
+                None => Metric::Unavailable(format!("Fan \"{fan}\" not found")),
+            };
+        }
+
+        fans.iter()
+            .find(|reading| reading.label.to_ascii_lowercase().contains("cpu"))
+            .map(|reading| Metric::Value(reading.rpm))
+            .unwrap_or_else(|| Metric::Unavailable(String::from("No CPU fan label found, set cpu.fan in the config")))
+    }
+}
+
+/// Components are labelled `chip label` (e.g. "k10temp Tctl"), or just the chip when it has a single channel.
+fn component_reading(component_label: &str, celsius: f32) -> TemperatureReading {
+    let (chip, label) = component_label.split_once(' ').unwrap_or((component_label, ""));
+    TemperatureReading {
+        chip: chip.to_string(),
+        label: label.to_string(),
+        celsius,
+    }
+}
+
+fn is_core_sensor(reading: &TemperatureReading) -> bool {
+    match reading.chip.as_str() {
+        "coretemp" => reading.label.starts_with("Core "),
+        "k10temp" | "zenpower" => reading.label.starts_with("Tccd"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_component_labels_into_chip_and_label() {
+        let reading = component_reading("k10temp Tctl", 61.0);
+        assert_eq!((reading.chip.as_str(), reading.label.as_str()), ("k10temp", "Tctl"));
+
+        let reading = component_reading("coretemp Package id 0", 55.0);
+        assert_eq!((reading.chip.as_str(), reading.label.as_str()), ("coretemp", "Package id 0"));
+
+        let reading = component_reading("acpitz", 40.0);
+        assert_eq!((reading.chip.as_str(), reading.label.as_str()), ("acpitz", ""));
+    }
+
+    #[test]
+    fn picks_a_package_sensor_from_component_readings() {
+        let monitor = CpuMonitor::new(None, None);
+        let readings = [component_reading("k10temp Tccd1", 58.0), component_reading("k10temp Tctl", 63.5)];
+        assert_eq!(monitor.package_temperature(&readings).value(), Some(&63.5));
+    }
+
+    #[test]
+    fn configured_sensor_matches_the_full_label() {
+        let monitor = CpuMonitor::new(Some(String::from("K10TEMP tccd1")), None);
+        let readings = [component_reading("k10temp Tccd1", 58.0), component_reading("k10temp Tctl", 63.5)];
+        assert_eq!(monitor.package_temperature(&readings).value(), Some(&58.0));
+    }
+}
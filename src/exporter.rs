@@ -0,0 +1,298 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+use crate::collector::{LatestSnapshot, Snapshot};
+use crate::gpu::ThrottleReason;
+
+const PREFIX: &str = "stats";
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// A scraper that stalls mid-request must not block the next one for long.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves `GET /metrics` in the Prometheus text format from the latest snapshot, on a background thread.
+/// Requests are handled one at a time; a scrape only formats what the sampling loop already collected.
+pub fn spawn(listen: SocketAddr, latest: LatestSnapshot) -> io::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+
+    thread::Builder::new()
+        .name(String::from("metrics-exporter"))
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // Scrapers that hang up or time out are routine; nothing to report on stderr for each one.
+                let _ = handle(stream, &latest);
+            }
+        })?;
+
+    Ok(())
+}
+
+fn handle(stream: TcpStream, latest: &LatestSnapshot) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are ignored, but have to be read before answering.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render(&latest.get())),
+        (Some("GET"), Some(_)) => ("404 Not Found", String::from("Only /metrics is served\n")),
+        _ => ("405 Method Not Allowed", String::from("Only GET is supported\n")),
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, CONTENT_TYPE, body.len(), body
+    )?;
+    stream.flush()
+}
+
+/// One gauge family: its samples as `(labels, value)`, skipping readings that are unavailable.
+struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    samples: Vec<(String, f64)>,
+}
+
+impl Gauge {
+    fn new(name: &'static str, help: &'static str) -> Self {
+        Self { name, help, samples: Vec::new() }
+    }
+
+    fn add(&mut self, labels: &str, value: Option<f64>) {
+        if let Some(value) = value {
+            self.samples.push((labels.to_string(), value));
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        if self.samples.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, self.name, self.help);
+        let _ = writeln!(out, "# TYPE {}_{} gauge", PREFIX, self.name);
+        for (labels, value) in &self.samples {
+            let _ = match labels.is_empty() {
+                true => writeln!(out, "{}_{} {}", PREFIX, self.name, value),
+                false => writeln!(out, "{}_{}{{{}}} {}", PREFIX, self.name, labels, value),
+            };
+        }
+    }
+}
+
+fn render(snapshot: &Snapshot) -> String {
+    let mut last_sample_timestamp_seconds = Gauge::new("last_sample_timestamp_seconds", "Unix time of the snapshot.");
+    let mut gpu_info = Gauge::new("gpu_info", "Always 1, labelled with the GPU's identity.");
+    let mut gpu_utilization_percent = Gauge::new("gpu_utilization_percent", "Share of time a kernel was running on the GPU.");
+    let mut gpu_memory_utilization_percent = Gauge::new("gpu_memory_utilization_percent", "Share of time device memory was read or written.");
+    let mut gpu_encoder_utilization_percent = Gauge::new("gpu_encoder_utilization_percent", "NVENC utilization.");
+    let mut gpu_decoder_utilization_percent = Gauge::new("gpu_decoder_utilization_percent", "NVDEC utilization.");
+    let mut gpu_used_memory_bytes = Gauge::new("gpu_used_memory_bytes", "VRAM in use.");
+    let mut gpu_total_memory_bytes = Gauge::new("gpu_total_memory_bytes", "Total VRAM.");
+    let mut gpu_used_memory_percent = Gauge::new("gpu_used_memory_percent", "VRAM in use, in percent.");
+    let mut gpu_temperature_celsius = Gauge::new("gpu_temperature_celsius", "GPU core temperature.");
+    let mut gpu_core_clock_megahertz = Gauge::new("gpu_core_clock_megahertz", "Graphics clock.");
+    let mut gpu_memory_clock_megahertz = Gauge::new("gpu_memory_clock_megahertz", "Memory clock.");
+    let mut gpu_performance_state = Gauge::new("gpu_performance_state", "Current P-state, from 0 (maximum performance) to 15.");
+    let mut gpu_throttle_reason = Gauge::new("gpu_throttle_reason", "1 while clocks are throttled for the labelled reason, else 0.");
+    let mut gpu_fan_speed_percent = Gauge::new("gpu_fan_speed_percent", "Fan speed as a percentage of its maximum.");
+    let mut gpu_power_usage_watts = Gauge::new("gpu_power_usage_watts", "Board power draw.");
+    let mut cpu_usage_percent = Gauge::new("cpu_usage_percent", "Total CPU usage.");
+    let mut cpu_core_usage_percent = Gauge::new("cpu_core_usage_percent", "Usage per logical core.");
+    let mut cpu_core_frequency_megahertz = Gauge::new("cpu_core_frequency_megahertz", "Current frequency per logical core.");
+    let mut cpu_temperature_celsius = Gauge::new("cpu_temperature_celsius", "CPU package temperature.");
+    let mut cpu_sensor_temperature_celsius = Gauge::new("cpu_sensor_temperature_celsius", "Per-core or per-CCD temperature sensors.");
+    let mut cpu_fan_rpm = Gauge::new("cpu_fan_rpm", "Speed of the fan mapped as the CPU fan.");
+    let mut fan_rpm = Gauge::new("fan_rpm", "Speed of every fan found.");
+    let mut memory_used_bytes = Gauge::new("memory_used_bytes", "RAM in use.");
+    let mut memory_total_bytes = Gauge::new("memory_total_bytes", "Total RAM.");
+    let mut memory_available_bytes = Gauge::new("memory_available_bytes", "RAM available for new allocations.");
+    let mut memory_used_percent = Gauge::new("memory_used_percent", "RAM in use, in percent.");
+    let mut memory_page_cache_bytes = Gauge::new("memory_page_cache_bytes", "RAM used by the page cache.");
+    let mut swap_used_bytes = Gauge::new("swap_used_bytes", "Swap in use.");
+    let mut swap_total_bytes = Gauge::new("swap_total_bytes", "Total swap.");
+    let mut monitor_cpu_usage_percent = Gauge::new("monitor_cpu_usage_percent", "The monitor's own CPU usage, in percent of one core.");
+    let mut monitor_memory_megabytes = Gauge::new("monitor_memory_megabytes", "The monitor's own resident memory.");
+    let mut monitor_sample_seconds = Gauge::new("monitor_sample_seconds", "Wall time of the last sampling pass.");
+
+    let timestamp = snapshot.timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+    last_sample_timestamp_seconds.add("", Some(timestamp.as_secs_f64()));
+
+    for gpu_stats in snapshot.gpu_stats.value().into_iter().flatten() {
+        let labels = format!(
+            "index=\"{}\",name=\"{}\",uuid=\"{}\"",
+            gpu_stats.index, escape(&gpu_stats.name), escape(&gpu_stats.uuid)
+        );
+        let info_labels = match gpu_stats.pci_bus_id.value() {
+            Some(pci_bus_id) => format!("{},pci_bus_id=\"{}\"", labels, escape(pci_bus_id)),
+            None => labels.clone(),
+        };
+        gpu_info.add(&info_labels, Some(1.0));
+        gpu_utilization_percent.add(&labels, gpu_stats.utilization.value().map(|v| *v as f64));
+        gpu_memory_utilization_percent.add(&labels, gpu_stats.memory_utilization.value().map(|v| *v as f64));
+        gpu_encoder_utilization_percent.add(&labels, gpu_stats.encoder_utilization.value().map(|v| *v as f64));
+        gpu_decoder_utilization_percent.add(&labels, gpu_stats.decoder_utilization.value().map(|v| *v as f64));
+        gpu_used_memory_bytes.add(&labels, gpu_stats.used_memory_bytes.value().map(|v| *v as f64));
+        gpu_total_memory_bytes.add(&labels, gpu_stats.total_memory_bytes.value().map(|v| *v as f64));
+        gpu_used_memory_percent.add(&labels, gpu_stats.used_memory_percentage.value().map(|v| *v as f64));
+        gpu_temperature_celsius.add(&labels, gpu_stats.temperature.value().map(|v| *v as f64));
+        gpu_core_clock_megahertz.add(&labels, gpu_stats.core_clock.value().map(|v| *v as f64));
+        gpu_memory_clock_megahertz.add(&labels, gpu_stats.memory_clock.value().map(|v| *v as f64));
+        gpu_performance_state.add(&labels, gpu_stats.performance_state.value().map(|v| *v as f64));
+        if let Some(reasons) = gpu_stats.throttle_reasons.value() {
+            for reason in ThrottleReason::ALL {
+                let active = reasons.contains(&reason) as u8 as f64;
+                gpu_throttle_reason.add(&format!("{},reason=\"{}\"", labels, reason.key()), Some(active));
+            }
+        }
+        gpu_fan_speed_percent.add(&labels, gpu_stats.fan_speed.value().map(|v| *v as f64));
+        gpu_power_usage_watts.add(&labels, gpu_stats.power_usage.value().copied());
+    }
+
+    let cpu_stats = &snapshot.cpu_stats;
+    cpu_usage_percent.add("", cpu_stats.total_usage.value().map(|v| *v as f64));
+    for (index, core) in cpu_stats.cores.value().into_iter().flatten().enumerate() {
+        let labels = format!("core=\"{}\"", index);
+        cpu_core_usage_percent.add(&labels, Some(core.usage as f64));
+        cpu_core_frequency_megahertz.add(&labels, Some(core.frequency as f64));
+    }
+    cpu_temperature_celsius.add("", cpu_stats.temperature.value().map(|v| *v as f64));
+    for (label, temp) in &cpu_stats.core_temperatures {
+        cpu_sensor_temperature_celsius.add(&format!("sensor=\"{}\"", escape(label)), Some(*temp as f64));
+    }
+    cpu_fan_rpm.add("", cpu_stats.fan_speed.value().map(|v| *v as f64));
+    for (label, rpm) in &cpu_stats.fans {
+        fan_rpm.add(&format!("fan=\"{}\"", escape(label)), Some(*rpm as f64));
+    }
+
+    if let Some(memory_stats) = snapshot.memory_stats.value() {
+        memory_used_bytes.add("", Some(memory_stats.used_memory_bytes as f64));
+        memory_total_bytes.add("", Some(memory_stats.total_memory_bytes as f64));
+        memory_available_bytes.add("", Some(memory_stats.available_memory_bytes as f64));
+        memory_used_percent.add("", Some(memory_stats.used_memory_percentage as f64));
+        memory_page_cache_bytes.add("", memory_stats.page_cache_bytes.value().map(|v| *v as f64));
+        swap_used_bytes.add("", Some(memory_stats.used_swap_bytes as f64));
+        swap_total_bytes.add("", Some(memory_stats.total_swap_bytes as f64));
+    }
+
+    let self_stats = &snapshot.self_stats;
+    monitor_cpu_usage_percent.add("", Some(self_stats.cpu_usage as f64));
+    monitor_memory_megabytes.add("", Some(self_stats.memory_megabytes as f64));
+    monitor_sample_seconds.add("", Some(self_stats.sample_time.as_secs_f64()));
+
+    let mut out = String::new();
+    for gauge in [
+        last_sample_timestamp_seconds,
+        gpu_info,
+        gpu_utilization_percent,
+        gpu_memory_utilization_percent,
+        gpu_encoder_utilization_percent,
+        gpu_decoder_utilization_percent,
+        gpu_used_memory_bytes,
+        gpu_total_memory_bytes,
+        gpu_used_memory_percent,
+        gpu_temperature_celsius,
+        gpu_core_clock_megahertz,
+        gpu_memory_clock_megahertz,
+        gpu_performance_state,
+        gpu_throttle_reason,
+        gpu_fan_speed_percent,
+        gpu_power_usage_watts,
+        cpu_usage_percent,
+        cpu_core_usage_percent,
+        cpu_core_frequency_megahertz,
+        cpu_temperature_celsius,
+        cpu_sensor_temperature_celsius,
+        cpu_fan_rpm,
+        fan_rpm,
+        memory_used_bytes,
+        memory_total_bytes,
+        memory_available_bytes,
+        memory_used_percent,
+        memory_page_cache_bytes,
+        swap_used_bytes,
+        swap_total_bytes,
+        monitor_cpu_usage_percent,
+        monitor_memory_megabytes,
+        monitor_sample_seconds,
+    ] {
+        gauge.render(&mut out);
+    }
+    out
+}
+
+/// Escapes a label value as the text format requires.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::{FakeGpuBackend, GpuBackend};
+    use crate::memory::MemoryStats;
+    use crate::metric::Metric;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            timestamp: UNIX_EPOCH + Duration::from_millis(1_500),
+            gpu_stats: FakeGpuBackend::demo().sample().into(),
+            memory_stats: Metric::Value(MemoryStats {
+                used_memory_bytes: 4_294_967_296,
+                total_memory_bytes: 17_179_869_184,
+                page_cache_bytes: Metric::Unavailable(String::from("No Cached entry")),
+                ..MemoryStats::default()
+            }),
+            ..Snapshot::default()
+        }
+    }
+
+    #[test]
+    fn renders_gauges_with_help_type_and_labels() {
+        let out = render(&snapshot());
+
+        assert!(out.starts_with("# HELP stats_last_sample_timestamp_seconds Unix time of the snapshot.\n# TYPE stats_last_sample_timestamp_seconds gauge\nstats_last_sample_timestamp_seconds 1.5\n"));
+        assert!(out.contains("stats_gpu_info{index=\"0\",name=\"Fake GPU\",uuid=\"GPU-fake-0\",pci_bus_id=\"00000000:01:00.0\"} 1\n"));
+        assert!(out.contains("stats_gpu_temperature_celsius{index=\"1\",name=\"Fake GPU\",uuid=\"GPU-fake-1\"} 78\n"));
+        assert!(out.contains("stats_gpu_throttle_reason{index=\"0\",name=\"Fake GPU\",uuid=\"GPU-fake-0\",reason=\"idle\"} 1\n"));
+        assert!(out.contains("stats_gpu_throttle_reason{index=\"0\",name=\"Fake GPU\",uuid=\"GPU-fake-0\",reason=\"power_cap\"} 0\n"));
+    }
+
+    #[test]
+    fn memory_is_exported_in_bytes() {
+        let out = render(&snapshot());
+
+        assert!(out.contains("# TYPE stats_memory_used_bytes gauge\nstats_memory_used_bytes 4294967296\n"));
+        assert!(out.contains("stats_memory_total_bytes 17179869184\n"));
+        assert!(out.contains("stats_gpu_total_memory_bytes{index=\"0\",name=\"Fake GPU\",uuid=\"GPU-fake-0\"} 25769803776\n"));
+        assert!(!out.contains("gigabytes"));
+    }
+
+    #[test]
+    fn unavailable_readings_are_left_out() {
+        let out = render(&snapshot());
+        // The second demo GPU reports no fan speed, and the page cache above is unavailable.
+        assert!(out.contains("stats_gpu_fan_speed_percent{index=\"0\""));
+        assert!(!out.contains("stats_gpu_fan_speed_percent{index=\"1\""));
+        assert!(!out.contains("stats_memory_page_cache_bytes"));
+
+        let out = render(&Snapshot::default());
+        assert!(!out.contains("stats_gpu_"));
+        assert!(!out.contains("stats_memory_"));
+        assert!(!out.contains("stats_cpu_usage_percent"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+}
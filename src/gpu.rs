@@ -0,0 +1,421 @@
+use nvml_wrapper::{Device, Nvml};
+use nvml_wrapper::bitmasks::device::ThrottleReasons;
+use nvml_wrapper::error::NvmlError;
+use nvml_wrapper::enum_wrappers::device::{TemperatureSensor, Clock, PerformanceState};
+use nvml_wrapper::enums::device::UsedGpuMemory;
+use crate::metric::{bytes_to_gigabytes, Metric};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Environment variable used to pick a GPU backend: `nvml` (default), `none` or `fake`.
+pub const BACKEND_ENV_VAR: &str = "STATS_GPU_BACKEND";
+
+/// Stats for one device. Identity fields fall back to placeholders; every measurement is read independently
+/// so an unsupported sensor only blanks out its own value.
+#[derive(Clone, Serialize)]
+pub struct GpuStats {
+    pub index: u32,
+    pub uuid: String,
+    pub pci_bus_id: Metric<String>,
+    pub name: String,
+    /// Share of time a kernel was running over the driver's last sample period, in percent.
+    pub utilization: Metric<u32>,
+    /// Share of time device memory was read or written, in percent.
+    pub memory_utilization: Metric<u32>,
+    /// NVENC load, in percent.
+    pub encoder_utilization: Metric<u32>,
+    /// NVDEC load, in percent.
+    pub decoder_utilization: Metric<u32>,
+    pub used_memory: Metric<f32>,
+    pub total_memory: Metric<f32>,
+    pub used_memory_percentage: Metric<i8>,
+    #[serde(skip)]
+    pub used_memory_bytes: Metric<u64>,
+    #[serde(skip)]
+    pub total_memory_bytes: Metric<u64>,
+    pub temperature: Metric<u32>,
+    pub core_clock: Metric<u32>,
+    pub memory_clock: Metric<u32>,
+    /// 0 (P0, maximum performance) to 15.
+    pub performance_state: Metric<u32>,
+    /// Why clocks are held below their maximum right now; empty when they aren't.
+    pub throttle_reasons: Metric<Vec<ThrottleReason>>,
+    pub fan_speed: Metric<u32>,
+    pub power_usage: Metric<f64>,
+    /// Compute and graphics processes on this GPU, most VRAM first.
+    pub processes: Metric<Vec<GpuProcess>>,
+}
+
+/// Clock throttle reasons, with NVML's finer-grained flags folded into the ones worth telling apart.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleReason {
+    PowerCap,
+    ThermalSlowdown,
+    HwSlowdown,
+    SyncBoost,
+    Idle,
+}
+
+impl ThrottleReason {
+    pub const ALL: [ThrottleReason; 5] = [
+        ThrottleReason::PowerCap,
+        ThrottleReason::ThermalSlowdown,
+        ThrottleReason::HwSlowdown,
+        ThrottleReason::SyncBoost,
+        ThrottleReason::Idle,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThrottleReason::PowerCap => "Power Cap",
+            ThrottleReason::ThermalSlowdown => "Thermal",
+            ThrottleReason::HwSlowdown => "HW Slowdown",
+            ThrottleReason::SyncBoost => "Sync Boost",
+            ThrottleReason::Idle => "Idle",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            ThrottleReason::PowerCap => "power_cap",
+            ThrottleReason::ThermalSlowdown => "thermal_slowdown",
+            ThrottleReason::HwSlowdown => "hw_slowdown",
+            ThrottleReason::SyncBoost => "sync_boost",
+            ThrottleReason::Idle => "idle",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ThrottleReason::PowerCap => "The driver is lowering clocks to stay within the power limit",
+            ThrottleReason::ThermalSlowdown => "The GPU or its memory is above its maximum operating temperature",
+            ThrottleReason::HwSlowdown => "Clocks are cut by 2x or more, by overheating or an external power brake",
+            ThrottleReason::SyncBoost => "Held back by another GPU in its sync boost group",
+            ThrottleReason::Idle => "Nothing is running on the GPU",
+        }
+    }
+
+    /// Application and display clock settings are left out; those are configuration, not something happening.
+    fn decode(reasons: ThrottleReasons) -> Vec<ThrottleReason> {
+        let flags = |reason| match reason {
+            ThrottleReason::PowerCap => ThrottleReasons::SW_POWER_CAP,
+            ThrottleReason::ThermalSlowdown => ThrottleReasons::SW_THERMAL_SLOWDOWN | ThrottleReasons::HW_THERMAL_SLOWDOWN,
+            ThrottleReason::HwSlowdown => ThrottleReasons::HW_SLOWDOWN | ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN,
+            ThrottleReason::SyncBoost => ThrottleReasons::SYNC_BOOST,
+            ThrottleReason::Idle => ThrottleReasons::GPU_IDLE,
+        };
+        ThrottleReason::ALL.into_iter().filter(|reason| reasons.intersects(flags(*reason))).collect()
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct GpuProcess {
+    pub pid: u32,
+    /// Filled in by the collector from the process table; empty if the process is gone or not visible to us.
+    pub name: String,
+    pub used_memory_megabytes: Metric<f32>,
+    /// Share of the SM (3D/compute) time over the last sampling window, in percent.
+    pub sm_utilization: Metric<u32>,
+}
+
+/// Source of GPU statistics. Returns one entry per device, or an empty list when there is no GPU.
+pub trait GpuBackend: Send {
+    fn sample(&mut self) -> Result<Vec<GpuStats>, NvmlError>;
+}
+
+pub struct NvmlBackend {
+    nvml: Nvml,
+    /// Timestamp of the newest process utilization sample seen per device, so each pass only gets newer ones.
+    last_utilization_sample: HashMap<u32, u64>,
+}
+
+impl NvmlBackend {
+    pub fn new(nvml: Nvml) -> Self {
+        Self {
+            nvml,
+            last_utilization_sample: HashMap::new(),
+        }
+    }
+}
+
+impl GpuBackend for NvmlBackend {
+    fn sample(&mut self) -> Result<Vec<GpuStats>, NvmlError> {
+        Ok((0..self.nvml.device_count()?)
+            .map(|index| match self.nvml.device_by_index(index) {
+                Ok(device) => {
+                    let last_sample = self.last_utilization_sample.entry(index).or_default();
+                    sample_device(index, &device, last_sample)
+                }
+                // A device that fell off the bus only blanks out its own entry.
+                Err(e) => unavailable_device(index, &e),
+            })
+            .collect())
+    }
+}
+
+fn sample_device(index: u32, device: &Device, last_utilization_sample: &mut u64) -> GpuStats {
+    let memory_info = Metric::from(device.memory_info());
+    let utilization = Metric::from(device.utilization_rates());
+
+    GpuStats {
+        index,
+        uuid: device.uuid().unwrap_or_else(|_| format!("GPU-{index}")),
+        pci_bus_id: device.pci_info().map(|pci_info| pci_info.bus_id).into(),
+        name: device.name().unwrap_or_else(|_| String::from("Unknown GPU")),
+        utilization: utilization.clone().map(|utilization| utilization.gpu),
+        memory_utilization: utilization.map(|utilization| utilization.memory),
+        encoder_utilization: device.encoder_utilization().map(|info| info.utilization).into(),
+        decoder_utilization: device.decoder_utilization().map(|info| info.utilization).into(),
+        used_memory: memory_info.clone().map(|memory_info| bytes_to_gigabytes(memory_info.used)),
+        total_memory: memory_info.clone().map(|memory_info| bytes_to_gigabytes(memory_info.total)),
+        used_memory_percentage: memory_info.clone().map(|memory_info| ((memory_info.used as f32 / memory_info.total as f32) * 100.0).round() as i8),
+        used_memory_bytes: memory_info.clone().map(|memory_info| memory_info.used),
+        total_memory_bytes: memory_info.map(|memory_info| memory_info.total),
+        temperature: device.temperature(TemperatureSensor::Gpu).into(),
+        core_clock: device.clock_info(Clock::Graphics).into(),
+        memory_clock: device.clock_info(Clock::Memory).into(),
+        performance_state: match device.performance_state() {
+            Ok(PerformanceState::Unknown) => Metric::Unavailable(String::from("Unknown")),
+            state => state.map(|state| state.as_c()).into(),
+        },
+        throttle_reasons: device.current_throttle_reasons().map(ThrottleReason::decode).into(),
+        fan_speed: device.fan_speed(0).into(),
+        power_usage: device.power_usage().map(|milliwatts| milliwatts as f64 / 1000.0).into(),
+        processes: sample_processes(device, last_utilization_sample).into(),
+    }
+}
+
+/// Placeholder for a device that can't be opened, with every reading unavailable for the same reason.
+fn unavailable_device(index: u32, error: &NvmlError) -> GpuStats {
+    let reason = error.to_string();
+    GpuStats {
+        index,
+        uuid: format!("GPU-{index}"),
+        pci_bus_id: Metric::Unavailable(reason.clone()),
+        name: String::from("Unknown GPU"),
+        utilization: Metric::Unavailable(reason.clone()),
+        memory_utilization: Metric::Unavailable(reason.clone()),
+        encoder_utilization: Metric::Unavailable(reason.clone()),
+        decoder_utilization: Metric::Unavailable(reason.clone()),
+        used_memory: Metric::Unavailable(reason.clone()),
+        total_memory: Metric::Unavailable(reason.clone()),
+        used_memory_percentage: Metric::Unavailable(reason.clone()),
+        used_memory_bytes: Metric::Unavailable(reason.clone()),
+        total_memory_bytes: Metric::Unavailable(reason.clone()),
+        temperature: Metric::Unavailable(reason.clone()),
+        core_clock: Metric::Unavailable(reason.clone()),
+        memory_clock: Metric::Unavailable(reason.clone()),
+        performance_state: Metric::Unavailable(reason.clone()),
+        throttle_reasons: Metric::Unavailable(reason.clone()),
+        fan_speed: Metric::Unavailable(reason.clone()),
+        power_usage: Metric::Unavailable(reason.clone()),
+        processes: Metric::Unavailable(reason),
+    }
+}
+
+/// Joins the running compute and graphics processes with their SM utilization since the previous pass.
+fn sample_processes(device: &Device, last_utilization_sample: &mut u64) -> Result<Vec<GpuProcess>, NvmlError> {
+    let mut used_memory = HashMap::new();
+    for process in device.running_compute_processes()?.into_iter().chain(device.running_graphics_processes()?) {
+        let memory = match process.used_gpu_memory {
+            UsedGpuMemory::Used(bytes) => Some(bytes),
+            UsedGpuMemory::Unavailable => None,
+        };
+        let entry = used_memory.entry(process.pid).or_insert(memory);
+        *entry = (*entry).max(memory);
+    }
+
+    // NotFound just means no process used the SMs since the last sample.
+    let utilization_samples = match device.process_utilization_stats(*last_utilization_sample) {
+        Ok(samples) => Ok(samples),
+        Err(NvmlError::NotFound) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    };
+    let mut sm_utilization = HashMap::new();
+    if let Ok(samples) = &utilization_samples {
+        for sample in samples {
+            *last_utilization_sample = (*last_utilization_sample).max(sample.timestamp);
+            sm_utilization.insert(sample.pid, sample.sm_util);
+        }
+    }
+
+    let mut processes: Vec<GpuProcess> = used_memory
+        .into_iter()
+        .map(|(pid, memory)| GpuProcess {
+            pid,
+            name: String::new(),
+            used_memory_megabytes: match memory {
+                Some(bytes) => Metric::Value(bytes as f32 / 1024.0 / 1024.0),
+                None => Metric::Unavailable(String::from("Not reported by the driver")),
+            },
+            sm_utilization: match &utilization_samples {
+                Ok(_) => Metric::Value(sm_utilization.get(&pid).copied().unwrap_or(0)),
+                Err(e) => Metric::Unavailable(e.to_string()),
+            },
+        })
+        .collect();
+    processes.sort_by(|a, b| {
+        let memory = |process: &GpuProcess| process.used_memory_megabytes.value().copied().unwrap_or_default();
+        memory(b).total_cmp(&memory(a))
+    });
+    Ok(processes)
+}
+
+/// Backend for machines without a supported GPU; the overlay only shows CPU stats.
+pub struct NoGpuBackend;
+
+impl GpuBackend for NoGpuBackend {
+    fn sample(&mut self) -> Result<Vec<GpuStats>, NvmlError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Replays a fixed script of samples in a loop, for running the app without GPU hardware.
+pub struct FakeGpuBackend {
+    frames: Vec<Vec<GpuStats>>,
+    next: usize,
+}
+
+impl FakeGpuBackend {
+    pub fn new(frames: Vec<Vec<GpuStats>>) -> Self {
+        Self { frames, next: 0 }
+    }
+
+    /// A short script of two cards ramping up under load and cooling back down, out of phase.
+    /// The second card is passively cooled and reports no fan speed; only the first one is encoding.
+    pub fn demo() -> Self {
+        let script = [
+            (8, 42, 1410, 30, 95.0, 3),
+            (55, 61, 1830, 48, 210.5, 64),
+            (91, 78, 1950, 72, 318.2, 99),
+            (34, 66, 1605, 55, 160.0, 41),
+        ];
+        let frames = (0..script.len())
+            .map(|step| {
+                (0..2)
+                    .map(|index| fake_device(index, script[(step + index as usize * 2) % script.len()]))
+                    .collect()
+            })
+            .collect();
+
+        Self::new(frames)
+    }
+}
+
+fn fake_device(
+    index: u32,
+    (memory_percentage, temperature, core_clock, fan_speed, power_usage, utilization): (i8, u32, u32, u32, f64, u32),
+) -> GpuStats {
+    let total_memory_bytes: u64 = 24 * 1024 * 1024 * 1024;
+    let used_memory_bytes = total_memory_bytes / 100 * memory_percentage as u64;
+    GpuStats {
+        index,
+        uuid: format!("GPU-fake-{index}"),
+        pci_bus_id: Metric::Value(format!("00000000:{:02X}:00.0", index + 1)),
+        name: String::from("Fake GPU"),
+        utilization: Metric::Value(utilization),
+        memory_utilization: Metric::Value(utilization / 2),
+        encoder_utilization: Metric::Value(match index {
+            0 => utilization * 2 / 3,
+            _ => 0,
+        }),
+        decoder_utilization: Metric::Value(0),
+        used_memory: Metric::Value(bytes_to_gigabytes(used_memory_bytes)),
+        total_memory: Metric::Value(bytes_to_gigabytes(total_memory_bytes)),
+        used_memory_percentage: Metric::Value(memory_percentage),
+        used_memory_bytes: Metric::Value(used_memory_bytes),
+        total_memory_bytes: Metric::Value(total_memory_bytes),
+        temperature: Metric::Value(temperature),
+        core_clock: Metric::Value(core_clock),
+        memory_clock: Metric::Value(10501),
+        performance_state: Metric::Value(match utilization {
+            0..10 => 8,
+            _ => 2,
+        }),
+        throttle_reasons: Metric::Value(
+            [
+                (utilization < 10, ThrottleReason::Idle),
+                (power_usage > 300.0, ThrottleReason::PowerCap),
+                (temperature >= 78, ThrottleReason::ThermalSlowdown),
+            ]
+            .into_iter()
+            .filter_map(|(active, reason)| active.then_some(reason))
+            .collect(),
+        ),
+        fan_speed: match index {
+            0 => Metric::Value(fan_speed),
+            _ => Metric::Unavailable(NvmlError::NotSupported.to_string()),
+        },
+        power_usage: Metric::Value(power_usage),
+        // Attributed to the monitor itself so the name lookup has a live process to find.
+        processes: Metric::Value(vec![
+            GpuProcess {
+                pid: std::process::id(),
+                name: String::new(),
+                used_memory_megabytes: Metric::Value(used_memory_bytes as f32 / 1024.0 / 1024.0 * 0.8),
+                sm_utilization: Metric::Value(memory_percentage as u32),
+            },
+        ]),
+    }
+}
+
+impl GpuBackend for FakeGpuBackend {
+    fn sample(&mut self) -> Result<Vec<GpuStats>, NvmlError> {
+        if self.frames.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let stats = self.frames[self.next].clone();
+        self.next = (self.next + 1) % self.frames.len();
+        Ok(stats)
+    }
+}
+
+/// Picks a backend from `STATS_GPU_BACKEND` or else `configured`, falling back to `NoGpuBackend` when NVML is unavailable.
+pub fn init_backend(configured: Option<&str>) -> Box<dyn GpuBackend> {
+    let requested = std::env::var(BACKEND_ENV_VAR).ok();
+    match requested.as_deref().or(configured) {
+        Some("none") => return Box::new(NoGpuBackend),
+        Some("fake") => return Box::new(FakeGpuBackend::demo()),
+        _ => {}
+    }
+
+    match Nvml::init() {
+        Ok(nvml) => Box::new(NvmlBackend::new(nvml)),
+        Err(e) => {
+            eprintln!("Failed to initialize NVML, GPU stats disabled: {:?}", e);
+            Box::new(NoGpuBackend)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(reasons: ThrottleReasons) -> Vec<&'static str> {
+        ThrottleReason::decode(reasons).into_iter().map(ThrottleReason::key).collect()
+    }
+
+    #[test]
+    fn decodes_nothing_when_running_freely() {
+        assert!(decode(ThrottleReasons::NONE).is_empty());
+        assert!(decode(ThrottleReasons::APPLICATIONS_CLOCKS_SETTING | ThrottleReasons::DISPLAY_CLOCK_SETTING).is_empty());
+    }
+
+    #[test]
+    fn folds_related_flags_into_one_reason() {
+        assert_eq!(decode(ThrottleReasons::SW_THERMAL_SLOWDOWN), vec!["thermal_slowdown"]);
+        assert_eq!(decode(ThrottleReasons::SW_THERMAL_SLOWDOWN | ThrottleReasons::HW_THERMAL_SLOWDOWN), vec!["thermal_slowdown"]);
+        assert_eq!(decode(ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN), vec!["hw_slowdown"]);
+        assert_eq!(decode(ThrottleReasons::HW_SLOWDOWN), vec!["hw_slowdown"]);
+    }
+
+    #[test]
+    fn lists_every_active_reason_in_order() {
+        let reasons = ThrottleReasons::GPU_IDLE | ThrottleReasons::SW_POWER_CAP | ThrottleReasons::SYNC_BOOST;
+        assert_eq!(decode(reasons), vec!["power_cap", "sync_boost", "idle"]);
+        assert_eq!(decode(ThrottleReasons::all()), ThrottleReason::ALL.map(ThrottleReason::key).to_vec());
+    }
+}
@@ -0,0 +1,79 @@
+use crate::cli::{Format, Options};
+use crate::collector::{LatestSnapshot, Sampler, Scope};
+use crate::config::Config;
+use crate::exporter;
+use crate::ipc;
+use crate::gpu::GpuBackend;
+use crate::recorder::Recorder;
+use crate::report::{self, Record};
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Samples on the calling thread and prints each snapshot in the requested format, for machines without a display.
+pub fn run(options: &Options, config: &Config, gpu: Box<dyn GpuBackend>) -> io::Result<()> {
+    let interval = options.interval.unwrap_or(config.refresh_interval());
+    let mut sampler = Sampler::new(gpu, config, Scope::full(config));
+
+    // CPU usage is measured between two refreshes, so the very first pass always reads 0%.
+    sampler.sample();
+    thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+
+    let latest = LatestSnapshot::new();
+    if let Some(listen) = config.exporter.listen {
+        if let Err(e) = exporter::spawn(listen, latest.clone()) {
+            eprintln!("Failed to start metrics exporter on {}: {}", listen, e);
+        }
+    }
+    // `--count` runs are for scripts and finish right away; they'd only race a running overlay for the socket.
+    // Other runs end on Ctrl-C, which skips the drop, so the next start clears the file as stale.
+    let _socket_file = match config.ipc.enabled && options.count.is_none() {
+        true => {
+            let path = config.ipc.socket.clone().unwrap_or_else(ipc::default_socket_path);
+            ipc::spawn(&path, latest.clone(), None)
+                .inspect_err(|e| eprintln!("Failed to open IPC socket {}: {}", path.display(), e))
+                .ok()
+        }
+        false => None,
+    };
+
+    let mut recorder = match options.record {
+        true => Some(Recorder::start(&config.recording)?),
+        false => None,
+    };
+
+    let mut stdout = io::stdout().lock();
+    let mut printed = 0;
+    loop {
+        latest.set(sampler.sample());
+        let snapshot = latest.get();
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(&snapshot, None)?;
+        }
+        match options.format {
+            Format::Table => {
+                let timestamp = snapshot.timestamp.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+                if printed > 0 {
+                    writeln!(stdout)?;
+                }
+                writeln!(stdout, "# {}.{:03}", timestamp.as_secs(), timestamp.subsec_millis())?;
+                write!(stdout, "{}", report::text(&snapshot, &config.units))?;
+            }
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut stdout, &Record::new(&snapshot))?;
+                writeln!(stdout)?;
+            }
+            Format::Ndjson => {
+                serde_json::to_writer(&mut stdout, &Record::new(&snapshot))?;
+                writeln!(stdout)?;
+            }
+        }
+        stdout.flush()?;
+
+        printed += 1;
+        if options.count.is_some_and(|count| printed >= count) {
+            return Ok(());
+        }
+        thread::sleep(interval);
+    }
+}
@@ -0,0 +1,121 @@
+use eframe::egui::{self, Color32, Sense, Shape, Stroke, Vec2};
+use std::collections::{HashMap, VecDeque};
+
+const SPARKLINE_SIZE: Vec2 = Vec2::new(48.0, 12.0);
+
+/// Bounded ring buffer of the most recent samples of one metric.
+pub struct History {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+pub struct Summary {
+    pub min: f32,
+    pub avg: f32,
+    pub max: f32,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            // Grows as samples arrive; a series that is hardly ever read shouldn't reserve its full length.
+            samples: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, value: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        while self.samples.len() > capacity {
+            self.samples.pop_front();
+        }
+        self.capacity = capacity;
+    }
+
+    pub fn summary(&self) -> Option<Summary> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let min = self.samples.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = self.samples.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let avg = self.samples.iter().sum::<f32>() / self.samples.len() as f32;
+        Some(Summary { min, avg, max })
+    }
+
+    /// Draws a small sparkline scaled to the window's min/max, with min/avg/max on hover.
+    pub fn show(&self, ui: &mut egui::Ui) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(SPARKLINE_SIZE, Sense::hover());
+        let Some(summary) = self.summary() else {
+            return response;
+        };
+
+        let range = (summary.max - summary.min).max(f32::EPSILON);
+        let step = rect.width() / (self.capacity.max(2) - 1) as f32;
+        let offset = self.capacity - self.samples.len();
+        let points = self.samples
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                egui::pos2(
+                    rect.left() + (offset + i) as f32 * step,
+                    rect.bottom() - (value - summary.min) / range * rect.height(),
+                )
+            })
+            .collect();
+
+        ui.painter().add(Shape::line(points, Stroke::new(1.0, Color32::from_rgb(120, 200, 120))));
+        response.on_hover_text(format!("min {:.1}  avg {:.1}  max {:.1}", summary.min, summary.avg, summary.max))
+    }
+}
+
+/// Histories for every metric, keyed by a stable name such as `gpu.<uuid>.temperature`.
+pub struct HistoryStore {
+    capacity: usize,
+    series: HashMap<String, History>,
+}
+
+impl HistoryStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Changes the window length, dropping the oldest samples if it shrinks.
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        for history in self.series.values_mut() {
+            history.resize(capacity);
+        }
+    }
+
+    /// Drops every sample, so min/avg/max start over.
+    pub fn clear(&mut self) {
+        self.series.clear();
+    }
+
+    /// Appends a sample; unavailable values are skipped rather than recorded as zero.
+    pub fn record(&mut self, key: String, value: Option<f32>) {
+        if let Some(value) = value {
+            let capacity = self.capacity;
+            self.series.entry(key).or_insert_with(|| History::new(capacity)).push(value);
+        }
+    }
+
+    /// Shows the sparkline for `key`, right-aligned in the current row.
+    pub fn show(&self, ui: &mut egui::Ui, key: &str) {
+        if let Some(history) = self.series.get(key) {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                history.show(ui);
+            });
+        }
+    }
+}
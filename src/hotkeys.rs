@@ -0,0 +1,83 @@
+use crate::config::{Config, ConfigError, HotkeyAction};
+use global_hotkey::{
+    GlobalHotKeyManager,
+    GlobalHotKeyEvent,
+    hotkey::HotKey,
+    HotKeyState,
+};
+use std::collections::HashMap;
+
+/// The registered global hotkeys and the action each one triggers.
+///
+/// A hotkey that can't be registered, usually because another application already grabbed it,
+/// is listed in `conflicts` and the others keep working.
+pub struct Hotkeys {
+    manager: Option<GlobalHotKeyManager>,
+    actions: HashMap<u32, HotkeyAction>,
+    registered: Vec<HotKey>,
+    conflicts: Vec<String>,
+}
+
+impl Hotkeys {
+    pub fn new() -> Self {
+        let (manager, conflicts) = match GlobalHotKeyManager::new() {
+            Ok(manager) => (Some(manager), Vec::new()),
+            Err(e) => (None, vec![format!("Global hotkeys unavailable: {}", e)]),
+        };
+
+        Self {
+            manager,
+            actions: HashMap::new(),
+            registered: Vec::new(),
+            conflicts,
+        }
+    }
+
+    /// Replaces the current bindings with the ones in `config`.
+    pub fn bind(&mut self, config: &Config) -> Result<(), ConfigError> {
+        let bindings = config.hotkey_bindings()?;
+        let Some(manager) = &self.manager else {
+            return Ok(());
+        };
+
+        let _ = manager.unregister_all(&self.registered);
+        self.registered.clear();
+        self.actions.clear();
+        self.conflicts.clear();
+
+        for (action, hotkey) in bindings {
+            match manager.register(hotkey) {
+                Ok(()) => {
+                    self.registered.push(hotkey);
+                    self.actions.insert(hotkey.id(), action);
+                }
+                Err(e) => self.conflicts.push(format!(
+                    "{} ({}): {}",
+                    config.hotkey_label(action).unwrap_or_default(),
+                    action.label(),
+                    e
+                )),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Actions of the hotkeys pressed since the last call.
+    pub fn pressed(&self) -> Vec<HotkeyAction> {
+        let mut pressed = Vec::new();
+        while let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
+            if event.state != HotKeyState::Pressed {
+                continue;
+            }
+            if let Some(action) = self.actions.get(&event.id) {
+                pressed.push(*action);
+            }
+        }
+        pressed
+    }
+
+    pub fn conflicts(&self) -> &[String] {
+        &self.conflicts
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::collector::{LatestSnapshot, Snapshot};
+use crate::report::Record;
+
+/// How often a subscriber checks for a new snapshot.
+const SUBSCRIBE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Commands that drive the overlay.
+#[derive(Clone, Copy)]
+pub enum Command {
+    Toggle,
+    Show,
+    Hide,
+}
+
+/// Runs a command on the overlay; absent in headless mode.
+pub type CommandHandler = Arc<dyn Fn(Command) + Send + Sync>;
+
+/// The bound socket file, removed when this is dropped. Drop only runs on a normal exit: headless mode
+/// without `--count` stops on SIGINT/SIGTERM, which ends the process without unwinding, so its file
+/// stays behind until the next instance removes it as stale.
+pub struct SocketFile {
+    path: PathBuf,
+}
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/system-stats-monitor.sock`, or the temp dir where there is no runtime dir.
+pub fn default_socket_path() -> PathBuf {
+    dirs::runtime_dir().unwrap_or_else(std::env::temp_dir).join("system-stats-monitor.sock")
+}
+
+/// Listens on a Unix domain socket for line-based commands, one connection per thread:
+///
+/// - `snapshot` answers the latest snapshot as one JSON line (same schema as `--format ndjson`)
+/// - `subscribe` streams every new snapshot as a JSON line until the client disconnects
+/// - `toggle`, `show`, `hide` change the overlay's visibility and answer `ok`
+///
+/// Anything else answers `error: ...`. The socket stays open for as long as the returned `SocketFile` lives.
+#[cfg(unix)]
+pub fn spawn(path: &Path, latest: LatestSnapshot, handler: Option<CommandHandler>) -> io::Result<SocketFile> {
+    remove_stale_socket(path)?;
+    let listener = UnixListener::bind(path)?;
+    let socket_file = SocketFile { path: path.to_path_buf() };
+
+    thread::Builder::new()
+        .name(String::from("ipc-listener"))
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let latest = latest.clone();
+                let handler = handler.clone();
+                let _ = thread::Builder::new()
+                    .name(String::from("ipc-client"))
+                    .spawn(move || {
+                        // Clients hanging up mid-stream is the normal way a subscription ends.
+                        let _ = serve(stream, &latest, handler.as_deref());
+                    });
+            }
+        })?;
+
+    Ok(socket_file)
+}
+
+#[cfg(not(unix))]
+pub fn spawn(_path: &Path, _latest: LatestSnapshot, _handler: Option<CommandHandler>) -> io::Result<SocketFile> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "the IPC socket needs Unix domain sockets"))
+}
+
+/// A socket file left behind by an instance that didn't exit cleanly is removed; one that still
+/// accepts connections belongs to a running instance and is left alone, and anything that isn't a
+/// socket is never touched.
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path.display())));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is used by another instance", path.display())));
+    }
+    fs::remove_file(path)
+}
+
+#[cfg(unix)]
+fn serve(stream: UnixStream, latest: &LatestSnapshot, handler: Option<&(dyn Fn(Command) + Send + Sync)>) -> io::Result<()> {
+    let mut writer = &stream;
+
+    for line in BufReader::new(&stream).lines() {
+        let line = line?;
+        let command = match line.trim() {
+            "" => continue,
+            "snapshot" => {
+                write_snapshot(&mut writer, &latest.get())?;
+                continue;
+            }
+            "subscribe" => return subscribe(&mut writer, latest),
+            "toggle" => Command::Toggle,
+            "show" => Command::Show,
+            "hide" => Command::Hide,
+            other => {
+                writeln!(writer, "error: unknown command \"{}\"", other)?;
+                continue;
+            }
+        };
+
+        match handler {
+            Some(handler) => {
+                handler(command);
+                writeln!(writer, "ok")?;
+            }
+            None => writeln!(writer, "error: no overlay in headless mode")?,
+        }
+    }
+
+    Ok(())
+}
+
+fn subscribe(writer: &mut impl Write, latest: &LatestSnapshot) -> io::Result<()> {
+    // Starts at the placeholder's sequence, so nothing is sent before the first real pass.
+    let mut sequence = 0;
+    loop {
+        let snapshot = latest.get();
+        if snapshot.sequence != sequence {
+            sequence = snapshot.sequence;
+            write_snapshot(writer, &snapshot)?;
+        }
+        thread::sleep(SUBSCRIBE_POLL_INTERVAL);
+    }
+}
+
+fn write_snapshot(writer: &mut impl Write, snapshot: &Snapshot) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, &Record::new(snapshot))?;
+    writeln!(writer)?;
+    writer.flush()
+}
@@ -0,0 +1,88 @@
+use serde::Serialize;
+use sysinfo::System;
+use crate::metric::{bytes_to_gigabytes, Metric};
+
+const MEMINFO_PATH: &str = "/proc/meminfo";
+
+/// Host memory, in gigabytes, with the raw byte counts kept for the exporter.
+#[derive(Serialize)]
+pub struct MemoryStats {
+    pub used_memory: f32,
+    pub total_memory: f32,
+    pub available_memory: f32,
+    pub used_memory_percentage: i8,
+    pub used_swap: f32,
+    pub total_swap: f32,
+    pub page_cache: Metric<f32>,
+    #[serde(skip)]
+    pub used_memory_bytes: u64,
+    #[serde(skip)]
+    pub total_memory_bytes: u64,
+    #[serde(skip)]
+    pub available_memory_bytes: u64,
+    #[serde(skip)]
+    pub used_swap_bytes: u64,
+    #[serde(skip)]
+    pub total_swap_bytes: u64,
+    #[serde(skip)]
+    pub page_cache_bytes: Metric<u64>,
+}
+
+impl Default for MemoryStats {
+    fn default() -> Self {
+        Self {
+            used_memory: 0.0,
+            total_memory: 0.0,
+            available_memory: 0.0,
+            used_memory_percentage: 0,
+            used_swap: 0.0,
+            total_swap: 0.0,
+            page_cache: Metric::Unavailable(String::from("Not sampled yet")),
+            used_memory_bytes: 0,
+            total_memory_bytes: 0,
+            available_memory_bytes: 0,
+            used_swap_bytes: 0,
+            total_swap_bytes: 0,
+            page_cache_bytes: Metric::Unavailable(String::from("Not sampled yet")),
+        }
+    }
+}
+
+pub fn sample(system: &System) -> MemoryStats {
+    let total_memory = system.total_memory();
+    let page_cache = read_page_cache();
+
+    MemoryStats {
+        used_memory: bytes_to_gigabytes(system.used_memory()),
+        total_memory: bytes_to_gigabytes(total_memory),
+        available_memory: bytes_to_gigabytes(system.available_memory()),
+        used_memory_percentage: match total_memory {
+            0 => 0,
+            total_memory => ((system.used_memory() as f32 / total_memory as f32) * 100.0).round() as i8,
+        },
+        used_swap: bytes_to_gigabytes(system.used_swap()),
+        total_swap: bytes_to_gigabytes(system.total_swap()),
+        page_cache: page_cache.clone().map(bytes_to_gigabytes),
+        used_memory_bytes: system.used_memory(),
+        total_memory_bytes: total_memory,
+        available_memory_bytes: system.available_memory(),
+        used_swap_bytes: system.used_swap(),
+        total_swap_bytes: system.total_swap(),
+        page_cache_bytes: page_cache,
+    }
+}
+
+/// `sysinfo` doesn't expose the page cache, so read the `Cached:` line of `/proc/meminfo`.
+fn read_page_cache() -> Metric<u64> {
+    let meminfo = match std::fs::read_to_string(MEMINFO_PATH) {
+        Ok(meminfo) => meminfo,
+        Err(e) => return Metric::Unavailable(format!("{MEMINFO_PATH}: {e}")),
+    };
+
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("Cached:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kilobytes| Metric::Value(kilobytes * 1024))
+        .unwrap_or_else(|| Metric::Unavailable(format!("No Cached entry in {MEMINFO_PATH}")))
+}
@@ -0,0 +1,62 @@
+use eframe::egui;
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
+/// A single sensor reading, or the reason it couldn't be read.
+#[derive(Clone)]
+pub enum Metric<T> {
+    Value(T),
+    Unavailable(String),
+}
+
+impl<T> Metric<T> {
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Metric::Value(value) => Some(value),
+            Metric::Unavailable(_) => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Metric<U> {
+        match self {
+            Metric::Value(value) => Metric::Value(f(value)),
+            Metric::Unavailable(reason) => Metric::Unavailable(reason),
+        }
+    }
+
+    /// Formats the value with `f`, or shows "N/A" with the reason as a tooltip.
+    pub fn show(&self, ui: &mut egui::Ui, f: impl FnOnce(&T) -> String) -> egui::Response {
+        match self {
+            Metric::Value(value) => ui.label(f(value)),
+            Metric::Unavailable(reason) => ui.label("N/A").on_hover_text(reason),
+        }
+    }
+
+    /// Plain-text counterpart of `show`, without the reason.
+    pub fn format(&self, f: impl FnOnce(&T) -> String) -> String {
+        match self {
+            Metric::Value(value) => f(value),
+            Metric::Unavailable(_) => String::from("N/A"),
+        }
+    }
+}
+
+/// Serialized as the bare value, or `null` when unavailable.
+impl<T: Serialize> Serialize for Metric<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value().serialize(serializer)
+    }
+}
+
+impl<T, E: Display> From<Result<T, E>> for Metric<T> {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(value) => Metric::Value(value),
+            Err(e) => Metric::Unavailable(e.to_string()),
+        }
+    }
+}
+
+pub fn bytes_to_gigabytes(bytes: u64) -> f32 {
+    bytes as f32 / 1024000000.0
+}
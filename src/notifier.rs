@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use zbus::zvariant::Value;
+use crate::alerts::{AlertEvent, Level};
+use crate::config::{NotificationConfig, UnitsConfig};
+
+const APP_NAME: &str = "System Stats Monitor";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Urgency {
+    Normal,
+    Critical,
+}
+
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+    /// Id of an earlier notification to replace in place.
+    pub replaces: Option<u32>,
+}
+
+/// Where notifications go. Returns the id the notification can later be replaced by.
+pub trait NotificationSink: Send {
+    fn send(&mut self, notification: &Notification) -> Result<u32, Box<dyn Error>>;
+}
+
+/// `org.freedesktop.Notifications` on the session bus.
+pub struct DbusNotifications {
+    connection: zbus::blocking::Connection,
+}
+
+impl DbusNotifications {
+    pub fn connect() -> zbus::Result<Self> {
+        Ok(Self { connection: zbus::blocking::Connection::session()? })
+    }
+}
+
+impl NotificationSink for DbusNotifications {
+    fn send(&mut self, notification: &Notification) -> Result<u32, Box<dyn Error>> {
+        let urgency: u8 = match notification.urgency {
+            Urgency::Normal => 1,
+            Urgency::Critical => 2,
+        };
+        let hints = HashMap::from([("urgency", Value::U8(urgency))]);
+        let actions: Vec<&str> = Vec::new();
+
+        let reply = self.connection.call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                APP_NAME,
+                notification.replaces.unwrap_or(0),
+                "",
+                notification.summary.as_str(),
+                notification.body.as_str(),
+                actions,
+                hints,
+                // Let the server decide; critical notifications usually stay until dismissed.
+                -1i32,
+            ),
+        )?;
+        Ok(reply.body().deserialize()?)
+    }
+}
+
+/// A reading that is currently critical.
+struct Breach {
+    label: String,
+    description: String,
+    since: Instant,
+    /// Id of the critical notification once it was sent.
+    notified: Option<u32>,
+}
+
+/// Raises a notification when a reading stays critical for `critical_seconds`, at most once per
+/// `min_interval_seconds` per reading, and replaces it with a "resolved" one when the reading recovers.
+/// Unlike the overlay, this works while the overlay is hidden.
+pub struct Notifier {
+    sink: Box<dyn NotificationSink>,
+    config: NotificationConfig,
+    breaches: HashMap<String, Breach>,
+    last_sent: HashMap<String, Instant>,
+}
+
+impl Notifier {
+    pub fn new(sink: Box<dyn NotificationSink>, config: NotificationConfig) -> Self {
+        Self {
+            sink,
+            config,
+            breaches: HashMap::new(),
+            last_sent: HashMap::new(),
+        }
+    }
+
+    pub fn set_config(&mut self, config: NotificationConfig) {
+        self.config = config;
+    }
+
+    /// Takes the level changes of the latest evaluation, then sends whatever became due. `is_critical` tells
+    /// whether a reading is still critical, so a breach is dropped silently once its reading isn't evaluated anymore.
+    pub fn update(&mut self, events: &[&AlertEvent], is_critical: impl Fn(&str) -> bool, units: &UnitsConfig) {
+        self.update_at(events, is_critical, units, Instant::now());
+    }
+
+    fn update_at(&mut self, events: &[&AlertEvent], is_critical: impl Fn(&str) -> bool, units: &UnitsConfig, now: Instant) {
+
+        for event in events {
+            if event.level == Level::Critical {
+                self.breaches.insert(event.key.clone(), Breach {
+                    label: event.label.clone(),
+                    description: event.describe(units),
+                    since: now,
+                    notified: None,
+                });
+            } else if event.previous == Level::Critical {
+                let Some(breach) = self.breaches.remove(&event.key) else {
+                    continue;
+                };
+                if let (Some(id), true) = (breach.notified, self.config.resolved) {
+                    self.send(&event.key, Notification {
+                        summary: format!("Resolved: {}", event.label),
+                        body: event.describe(units),
+                        urgency: Urgency::Normal,
+                        replaces: Some(id),
+                    }, now);
+                }
+            }
+        }
+
+        self.breaches.retain(|key, _| is_critical(key));
+
+        let sustain = Duration::from_secs(self.config.critical_seconds);
+        let min_interval = Duration::from_secs(self.config.min_interval_seconds);
+        let due: Vec<String> = self.breaches
+            .iter()
+            .filter(|(_, breach)| breach.notified.is_none() && now.duration_since(breach.since) >= sustain)
+            .filter(|(key, _)| self.last_sent.get(*key).is_none_or(|sent| now.duration_since(*sent) >= min_interval))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in due {
+            let breach = &self.breaches[&key];
+            let notification = Notification {
+                summary: format!("Critical: {}", breach.label),
+                body: format!("{} for {} s", breach.description, now.duration_since(breach.since).as_secs()),
+                urgency: Urgency::Critical,
+                replaces: None,
+            };
+            // A failed send is retried once the rate limit allows, not on every pass.
+            let id = self.send(&key, notification, now);
+            if let Some(breach) = self.breaches.get_mut(&key) {
+                breach.notified = id;
+            }
+        }
+    }
+
+    fn send(&mut self, key: &str, notification: Notification, now: Instant) -> Option<u32> {
+        self.last_sent.insert(key.to_string(), now);
+        match self.sink.send(&notification) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                eprintln!("Failed to send notification: {}", e);
+                None
+            }
+        }
+    }
+}
+
+enum Message {
+    Update {
+        events: Vec<AlertEvent>,
+        critical: HashSet<String>,
+        units: UnitsConfig,
+    },
+    Config(NotificationConfig),
+}
+
+/// Runs a `Notifier` on its own thread with the session bus connection, so a slow or still-activating
+/// notification daemon only ever blocks that thread and never the overlay's frames.
+pub struct NotifierThread {
+    messages: Sender<Message>,
+}
+
+impl NotifierThread {
+    pub fn spawn(config: NotificationConfig) -> io::Result<Self> {
+        let (messages, received) = mpsc::channel();
+
+        thread::Builder::new()
+            .name(String::from("notifier"))
+            .spawn(move || {
+                let sink = match DbusNotifications::connect() {
+                    Ok(sink) => sink,
+                    Err(e) => {
+                        eprintln!("Failed to connect to the session bus, notifications disabled: {}", e);
+                        return;
+                    }
+                };
+                let mut notifier = Notifier::new(Box::new(sink), config);
+
+                for message in received {
+                    match message {
+                        Message::Update { events, critical, units } => {
+                            let events: Vec<&AlertEvent> = events.iter().collect();
+                            notifier.update(&events, |key| critical.contains(key), &units);
+                        }
+                        Message::Config(config) => notifier.set_config(config),
+                    }
+                }
+            })?;
+
+        Ok(Self { messages })
+    }
+
+    /// Hands the latest evaluation to the notifier thread: the level changes and which readings are critical now.
+    pub fn update(&self, events: Vec<AlertEvent>, critical: HashSet<String>, units: UnitsConfig) {
+        // Fails only once the thread gave up on the session bus, and that was already reported.
+        let _ = self.messages.send(Message::Update { events, critical, units });
+    }
+
+    pub fn set_config(&self, config: NotificationConfig) {
+        let _ = self.messages.send(Message::Config(config));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AlertMetric;
+    use std::sync::{Arc, Mutex};
+    use std::time::SystemTime;
+
+    const KEY: &str = "cpu.usage";
+
+    /// `(summary, urgency, replaces)` of one send.
+    type Attempt = (String, Urgency, Option<u32>);
+
+    /// Records every attempt and hands out ids 1, 2, ... unless told to fail.
+    #[derive(Clone, Default)]
+    struct FakeSink {
+        attempts: Arc<Mutex<Vec<Attempt>>>,
+        failing: Arc<Mutex<bool>>,
+    }
+
+    impl NotificationSink for FakeSink {
+        fn send(&mut self, notification: &Notification) -> Result<u32, Box<dyn Error>> {
+            let mut attempts = self.attempts.lock().unwrap();
+            attempts.push((notification.summary.clone(), notification.urgency, notification.replaces));
+            match *self.failing.lock().unwrap() {
+                true => Err("daemon not running".into()),
+                false => Ok(attempts.len() as u32),
+            }
+        }
+    }
+
+    impl FakeSink {
+        fn attempts(&self) -> Vec<Attempt> {
+            self.attempts.lock().unwrap().clone()
+        }
+    }
+
+    fn notifier() -> (Notifier, FakeSink) {
+        let sink = FakeSink::default();
+        let config = NotificationConfig {
+            enabled: true,
+            critical_seconds: 30,
+            min_interval_seconds: 600,
+            resolved: true,
+        };
+        (Notifier::new(Box::new(sink.clone()), config), sink)
+    }
+
+    fn event(level: Level, previous: Level) -> AlertEvent {
+        AlertEvent {
+            time: SystemTime::now(),
+            key: String::from(KEY),
+            label: String::from("CPU usage"),
+            metric: AlertMetric::CpuUsage,
+            level,
+            previous,
+            value: 95.0,
+        }
+    }
+
+    /// Runs one update `seconds` after `start`, with `events` and KEY critical when `critical` is set.
+    fn update(notifier: &mut Notifier, start: Instant, seconds: u64, events: &[AlertEvent], critical: bool) {
+        let events: Vec<&AlertEvent> = events.iter().collect();
+        notifier.update_at(&events, |_| critical, &UnitsConfig::default(), start + Duration::from_secs(seconds));
+    }
+
+    #[test]
+    fn waits_for_critical_seconds() {
+        let (mut notifier, sink) = notifier();
+        let start = Instant::now();
+
+        update(&mut notifier, start, 0, &[event(Level::Critical, Level::Warning)], true);
+        update(&mut notifier, start, 29, &[], true);
+        assert!(sink.attempts().is_empty());
+
+        update(&mut notifier, start, 30, &[], true);
+        update(&mut notifier, start, 31, &[], true);
+        assert_eq!(sink.attempts(), [(String::from("Critical: CPU usage"), Urgency::Critical, None)]);
+    }
+
+    #[test]
+    fn recovering_before_critical_seconds_sends_nothing() {
+        let (mut notifier, sink) = notifier();
+        let start = Instant::now();
+
+        update(&mut notifier, start, 0, &[event(Level::Critical, Level::Normal)], true);
+        update(&mut notifier, start, 10, &[event(Level::Normal, Level::Critical)], false);
+        update(&mut notifier, start, 60, &[], false);
+        assert!(sink.attempts().is_empty());
+    }
+
+    #[test]
+    fn resolved_replaces_the_critical_notification() {
+        let (mut notifier, sink) = notifier();
+        let start = Instant::now();
+
+        update(&mut notifier, start, 0, &[event(Level::Critical, Level::Warning)], true);
+        update(&mut notifier, start, 30, &[], true);
+        update(&mut notifier, start, 40, &[event(Level::Warning, Level::Critical)], false);
+
+        let attempts = sink.attempts();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[1], (String::from("Resolved: CPU usage"), Urgency::Normal, Some(1)));
+    }
+
+    #[test]
+    fn rate_limits_per_reading() {
+        let (mut notifier, sink) = notifier();
+        let start = Instant::now();
+
+        update(&mut notifier, start, 0, &[event(Level::Critical, Level::Warning)], true);
+        update(&mut notifier, start, 30, &[], true);
+        update(&mut notifier, start, 40, &[event(Level::Warning, Level::Critical)], false);
+        assert_eq!(sink.attempts().len(), 2);
+
+        // Critical again and sustained, but the resolved notification went out less than 600 s ago.
+        update(&mut notifier, start, 50, &[event(Level::Critical, Level::Warning)], true);
+        update(&mut notifier, start, 100, &[], true);
+        update(&mut notifier, start, 639, &[], true);
+        assert_eq!(sink.attempts().len(), 2);
+
+        update(&mut notifier, start, 640, &[], true);
+        assert_eq!(sink.attempts().len(), 3);
+    }
+
+    #[test]
+    fn retries_a_failed_send_only_after_min_interval() {
+        let (mut notifier, sink) = notifier();
+        let start = Instant::now();
+        *sink.failing.lock().unwrap() = true;
+
+        update(&mut notifier, start, 0, &[event(Level::Critical, Level::Warning)], true);
+        update(&mut notifier, start, 30, &[], true);
+        update(&mut notifier, start, 31, &[], true);
+        update(&mut notifier, start, 629, &[], true);
+        assert_eq!(sink.attempts().len(), 1);
+
+        *sink.failing.lock().unwrap() = false;
+        update(&mut notifier, start, 630, &[], true);
+        update(&mut notifier, start, 631, &[], true);
+        assert_eq!(sink.attempts().len(), 2);
+    }
+
+    #[test]
+    fn drops_breaches_of_readings_no_longer_evaluated() {
+        let (mut notifier, sink) = notifier();
+        let start = Instant::now();
+
+        update(&mut notifier, start, 0, &[event(Level::Critical, Level::Warning)], true);
+        update(&mut notifier, start, 30, &[], false);
+        assert!(sink.attempts().is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users};
+use crate::config::{ProcessSort, ProcessesConfig};
+
+#[derive(Clone, Serialize)]
+pub struct ProcessStats {
+    pub pid: u32,
+    pub name: String,
+    pub user: Option<String>,
+    /// Percent of one core, like `top`.
+    pub cpu_usage: f32,
+    /// Resident set size.
+    pub memory_megabytes: f32,
+}
+
+/// Refreshes every process and keeps the top `count` by CPU and the top `count` by RSS, so the overlay can
+/// switch between both orders without another scan. Heaviest first by `config.sort`.
+pub fn sample(system: &mut System, users: &Users, config: &ProcessesConfig) -> Vec<ProcessStats> {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory().with_user(UpdateKind::OnlyIfNotSet),
+    );
+
+    let mut processes: Vec<ProcessStats> = system
+        .processes()
+        .values()
+        // Linux lists every thread as a task of its own; they would crowd out the processes.
+        .filter(|process| process.thread_kind().is_none())
+        .map(|process| ProcessStats {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+            user: process.user_id()
+                .and_then(|uid| users.get_user_by_id(uid))
+                .map(|user| user.name().to_string()),
+            cpu_usage: process.cpu_usage(),
+            memory_megabytes: process.memory() as f32 / 1024.0 / 1024.0,
+        })
+        .filter(|process| config.user.is_none() || process.user == config.user)
+        .collect();
+
+    sort(&mut processes, ProcessSort::Memory);
+    let top_memory: Vec<u32> = processes.iter().take(config.count).map(|process| process.pid).collect();
+    sort(&mut processes, ProcessSort::Cpu);
+    let mut kept = 0;
+    processes.retain(|process| {
+        kept += 1;
+        kept <= config.count || top_memory.contains(&process.pid)
+    });
+
+    sort(&mut processes, config.sort);
+    processes
+}
+
+pub fn sort(processes: &mut [ProcessStats], by: ProcessSort) {
+    match by {
+        ProcessSort::Cpu => processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage)),
+        ProcessSort::Memory => processes.sort_by(|a, b| b.memory_megabytes.total_cmp(&a.memory_megabytes)),
+    }
+}
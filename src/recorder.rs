@@ -0,0 +1,292 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::collector::Snapshot;
+use crate::config::RecordingConfig;
+
+/// Appends one CSV row per snapshot to the files of a session.
+///
+/// A session starts a new file (`session-<start>-<part>.csv`, start in Unix milliseconds) when the current one grows past the size
+/// or age limit, and when the set of columns changes, e.g. because a GPU appeared.
+pub struct Recorder {
+    dir: PathBuf,
+    /// Unix milliseconds, so a session restarted right away doesn't reuse the previous names.
+    started: u64,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    part: u32,
+    file: Option<SessionFile>,
+}
+
+struct SessionFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    header: Vec<String>,
+    bytes: u64,
+    opened: Instant,
+}
+
+impl Recorder {
+    /// `$XDG_DATA_HOME/system-stats-monitor` or the platform equivalent.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("system-stats-monitor"))
+    }
+
+    /// Prepares a session in the configured or default directory; the first file is created with the first row.
+    pub fn start(config: &RecordingConfig) -> io::Result<Self> {
+        let dir = config.dir.clone()
+            .or_else(Self::default_dir)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory, set recording.dir in the config"))?;
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            started: unix_millis(SystemTime::now()),
+            max_bytes: (config.max_file_mb > 0).then_some(config.max_file_mb * 1024 * 1024),
+            max_age: (config.max_file_minutes > 0).then_some(Duration::from_secs(config.max_file_minutes * 60)),
+            part: 0,
+            file: None,
+        })
+    }
+
+    /// Writes `snapshot` as a row. `fps` is left empty when there is no overlay to measure.
+    pub fn record(&mut self, snapshot: &Snapshot, fps: Option<u32>) -> io::Result<()> {
+        let (header, row): (Vec<String>, Vec<String>) = columns(snapshot, fps).into_iter().unzip();
+
+        let rotate = match &self.file {
+            Some(file) => {
+                file.header != header
+                    || self.max_bytes.is_some_and(|max_bytes| file.bytes >= max_bytes)
+                    || self.max_age.is_some_and(|max_age| file.opened.elapsed() >= max_age)
+            }
+            None => true,
+        };
+        if rotate {
+            self.part += 1;
+            self.file = Some(SessionFile::create(self.dir.join(format!("session-{}-{:03}.csv", self.started, self.part)), header)?);
+        }
+
+        let file = self.file.as_mut().expect("a file was just opened");
+        file.write_line(&row)?;
+        file.writer.flush()
+    }
+
+    /// The file currently written to, or the directory before the first row.
+    pub fn path(&self) -> &Path {
+        self.file.as_ref().map_or(&self.dir, |file| &file.path)
+    }
+}
+
+impl SessionFile {
+    fn create(path: PathBuf, header: Vec<String>) -> io::Result<Self> {
+        // Never truncates an existing file; a name clash fails the recording instead of losing data.
+        let mut file = Self {
+            writer: BufWriter::new(OpenOptions::new().write(true).create_new(true).open(&path)?),
+            path,
+            header: Vec::new(),
+            bytes: 0,
+            opened: Instant::now(),
+        };
+        file.write_line(&header)?;
+        file.header = header;
+        Ok(file)
+    }
+
+    fn write_line(&mut self, fields: &[String]) -> io::Result<()> {
+        let line = format!("{}\n", fields.join(","));
+        self.writer.write_all(line.as_bytes())?;
+        self.bytes += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// `(column, value)` for every field of the snapshot. Unavailable readings are empty.
+fn columns(snapshot: &Snapshot, fps: Option<u32>) -> Vec<(String, String)> {
+    fn optional<T: ToString>(value: Option<&T>) -> String {
+        value.map(T::to_string).unwrap_or_default()
+    }
+
+    let mut columns = vec![
+        (String::from("timestamp_ms"), unix_millis(snapshot.timestamp).to_string()),
+        (String::from("fps"), optional(fps.as_ref())),
+    ];
+
+    for gpu_stats in snapshot.gpu_stats.value().into_iter().flatten() {
+        let index = gpu_stats.index;
+        columns.extend([
+            (format!("gpu{index}_utilization_percentage"), optional(gpu_stats.utilization.value())),
+            (format!("gpu{index}_memory_utilization_percentage"), optional(gpu_stats.memory_utilization.value())),
+            (format!("gpu{index}_encoder_utilization_percentage"), optional(gpu_stats.encoder_utilization.value())),
+            (format!("gpu{index}_decoder_utilization_percentage"), optional(gpu_stats.decoder_utilization.value())),
+            (format!("gpu{index}_used_memory_gb"), optional(gpu_stats.used_memory.value())),
+            (format!("gpu{index}_total_memory_gb"), optional(gpu_stats.total_memory.value())),
+            (format!("gpu{index}_used_memory_percentage"), optional(gpu_stats.used_memory_percentage.value())),
+            (format!("gpu{index}_temperature_c"), optional(gpu_stats.temperature.value())),
+            (format!("gpu{index}_core_clock_mhz"), optional(gpu_stats.core_clock.value())),
+            (format!("gpu{index}_memory_clock_mhz"), optional(gpu_stats.memory_clock.value())),
+            (format!("gpu{index}_performance_state"), optional(gpu_stats.performance_state.value())),
+            (
+                format!("gpu{index}_throttle_reasons"),
+                gpu_stats.throttle_reasons.value()
+                    .map(|reasons| reasons.iter().map(|reason| reason.key()).collect::<Vec<_>>().join("|"))
+                    .unwrap_or_default(),
+            ),
+            (format!("gpu{index}_fan_speed_percentage"), optional(gpu_stats.fan_speed.value())),
+            (format!("gpu{index}_power_usage_w"), optional(gpu_stats.power_usage.value())),
+        ]);
+    }
+
+    let cpu_stats = &snapshot.cpu_stats;
+    columns.extend([
+        (String::from("cpu_usage"), optional(cpu_stats.total_usage.value())),
+        (String::from("cpu_temperature_c"), optional(cpu_stats.temperature.value())),
+        (String::from("cpu_fan_rpm"), optional(cpu_stats.fan_speed.value())),
+    ]);
+    for (index, core) in cpu_stats.cores.value().into_iter().flatten().enumerate() {
+        columns.push((format!("cpu{index}_usage"), format!("{:.1}", core.usage)));
+        columns.push((format!("cpu{index}_frequency_mhz"), core.frequency.to_string()));
+    }
+    for (label, temperature) in &cpu_stats.core_temperatures {
+        columns.push((format!("cpu_sensor_{}_c", column_name(label)), temperature.to_string()));
+    }
+    for (label, rpm) in &cpu_stats.fans {
+        columns.push((format!("fan_{}_rpm", column_name(label)), rpm.to_string()));
+    }
+
+    // Like GPUs, memory only gets columns while it is sampled.
+    if let Some(memory_stats) = snapshot.memory_stats.value() {
+        columns.extend([
+            (String::from("memory_used_gb"), memory_stats.used_memory.to_string()),
+            (String::from("memory_total_gb"), memory_stats.total_memory.to_string()),
+            (String::from("memory_used_percentage"), memory_stats.used_memory_percentage.to_string()),
+            (String::from("memory_available_gb"), memory_stats.available_memory.to_string()),
+            (String::from("memory_page_cache_gb"), optional(memory_stats.page_cache.value())),
+            (String::from("swap_used_gb"), memory_stats.used_swap.to_string()),
+            (String::from("swap_total_gb"), memory_stats.total_swap.to_string()),
+        ]);
+    }
+
+    columns
+}
+
+/// Sensor and fan labels as they are reported ("k10temp Tccd1", "nct6798 fan2") in a form safe for a CSV header.
+fn column_name(label: &str) -> String {
+    label.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect()
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as u64).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryStats;
+    use crate::metric::Metric;
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("system-stats-monitor-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn recorder(dir: &Path) -> Recorder {
+        Recorder::start(&RecordingConfig { dir: Some(dir.to_path_buf()), max_file_mb: 0, max_file_minutes: 0 }).unwrap()
+    }
+
+    /// File names in the order they were written, with their lines.
+    fn files(dir: &Path) -> Vec<(String, Vec<String>)> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let lines = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+                (path.file_name().unwrap().to_string_lossy().into_owned(), lines)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn appends_rows_to_one_file_without_limits() {
+        let dir = temp_dir("recorder-append");
+        let mut recorder = recorder(&dir);
+        assert_eq!(recorder.path(), dir);
+
+        for fps in [Some(60), None, Some(58)] {
+            recorder.record(&Snapshot::default(), fps).unwrap();
+        }
+
+        let files = files(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, format!("session-{}-001.csv", recorder.started));
+        let (_, lines) = &files[0];
+        assert!(lines[0].starts_with("timestamp_ms,fps,cpu_usage,"));
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("0,60,"));
+        assert!(lines[2].starts_with("0,,"));
+    }
+
+    #[test]
+    fn starts_a_new_file_when_the_columns_change() {
+        let dir = temp_dir("recorder-columns");
+        let mut recorder = recorder(&dir);
+
+        recorder.record(&Snapshot::default(), None).unwrap();
+        let with_memory = Snapshot { memory_stats: Metric::Value(MemoryStats::default()), ..Snapshot::default() };
+        recorder.record(&with_memory, None).unwrap();
+        recorder.record(&with_memory, None).unwrap();
+
+        let files = files(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].0.ends_with("-001.csv") && files[1].0.ends_with("-002.csv"));
+        assert!(!files[0].1[0].contains("memory_used_gb"));
+        assert!(files[1].1[0].contains("memory_used_gb"));
+        assert_eq!((files[0].1.len(), files[1].1.len()), (2, 3));
+        assert!(recorder.path().ends_with(&files[1].0));
+    }
+
+    #[test]
+    fn starts_a_new_file_past_the_size_limit() {
+        let dir = temp_dir("recorder-size");
+        let mut recorder = recorder(&dir);
+        // Any header is over a byte, so every row after the first one rotates.
+        recorder.max_bytes = Some(1);
+
+        for _ in 0..3 {
+            recorder.record(&Snapshot::default(), None).unwrap();
+        }
+
+        let files = files(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|(_, lines)| lines.len() == 2));
+    }
+
+    #[test]
+    fn never_overwrites_an_existing_file() {
+        let dir = temp_dir("recorder-existing");
+        let mut recorder = recorder(&dir);
+        let existing = dir.join(format!("session-{}-001.csv", recorder.started));
+        fs::write(&existing, "earlier session\n").unwrap();
+
+        let error = recorder.record(&Snapshot::default(), None).unwrap_err();
+
+        let contents = fs::read_to_string(&existing).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(contents, "earlier session\n");
+    }
+
+    #[test]
+    fn column_names_are_csv_safe() {
+        assert_eq!(column_name("k10temp Tccd1"), "k10temp_tccd1");
+        assert_eq!(column_name("nct6798 fan2"), "nct6798_fan2");
+        assert_eq!(column_name("coretemp Core 0, \"hot\""), "coretemp_core_0___hot_");
+    }
+}
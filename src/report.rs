@@ -0,0 +1,95 @@
+use crate::collector::Snapshot;
+use crate::config::UnitsConfig;
+use serde::Serialize;
+
+/// Bumped whenever a field of `Record` is renamed, removed or changes meaning. New fields don't bump it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One snapshot as emitted by `--format json|ndjson`. Temperatures are always in Celsius,
+/// memory in gigabytes, and readings that couldn't be taken are `null`.
+#[derive(Serialize)]
+pub struct Record<'a> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub snapshot: &'a Snapshot,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(snapshot: &'a Snapshot) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            snapshot,
+        }
+    }
+}
+
+/// The overlay's readings as `(label, value)` pairs, for plain-text output.
+pub fn rows(snapshot: &Snapshot, units: &UnitsConfig) -> Vec<(String, String)> {
+    let mut rows = Vec::new();
+
+    match snapshot.gpu_stats.value() {
+        Some(all_gpu_stats) => {
+            for gpu_stats in all_gpu_stats {
+                let prefix = format!("GPU {}", gpu_stats.index);
+                rows.push((prefix.clone(), gpu_stats.name.clone()));
+                rows.push((format!("{} Usage", prefix), gpu_stats.utilization.format(|utilization| format!("{}%", utilization))));
+                rows.push((format!("{} Memory Controller", prefix), gpu_stats.memory_utilization.format(|utilization| format!("{}%", utilization))));
+                rows.push((format!("{} Encoder", prefix), gpu_stats.encoder_utilization.format(|utilization| format!("{}%", utilization))));
+                rows.push((format!("{} Decoder", prefix), gpu_stats.decoder_utilization.format(|utilization| format!("{}%", utilization))));
+                rows.push((
+                    format!("{} VRAM", prefix),
+                    format!("{} / {} {}",
+                        gpu_stats.used_memory.format(|used_memory| format!("{:.1}", used_memory)),
+                        gpu_stats.total_memory.format(|total_memory| format!("{:.1} GB", total_memory)),
+                        gpu_stats.used_memory_percentage.format(|percentage| format!("({}%)", percentage))
+                    ),
+                ));
+                rows.push((format!("{} Temperature", prefix), gpu_stats.temperature.format(|temperature| units.temperature.format(*temperature as f32, 0))));
+                rows.push((format!("{} Core Clock", prefix), gpu_stats.core_clock.format(|core_clock| format!("{} MHz", core_clock))));
+                rows.push((format!("{} P-State", prefix), gpu_stats.performance_state.format(|state| format!("P{}", state))));
+                rows.push((
+                    format!("{} Throttle", prefix),
+                    gpu_stats.throttle_reasons.format(|reasons| match reasons.is_empty() {
+                        true => String::from("None"),
+                        false => reasons.iter().map(|reason| reason.label()).collect::<Vec<_>>().join(", "),
+                    }),
+                ));
+                rows.push((format!("{} Memory Clock", prefix), gpu_stats.memory_clock.format(|memory_clock| format!("{} MHz", memory_clock))));
+                rows.push((format!("{} Fan Speed", prefix), gpu_stats.fan_speed.format(|fan_speed| format!("{}%", fan_speed))));
+                rows.push((format!("{} Power", prefix), gpu_stats.power_usage.format(|power_usage| format!("{:.1} W", power_usage))));
+            }
+        }
+        None => rows.push((String::from("GPU"), String::from("N/A"))),
+    }
+
+    let cpu_stats = &snapshot.cpu_stats;
+    rows.push((String::from("CPU Usage"), cpu_stats.total_usage.format(|usage| format!("{}%", usage))));
+    if let Some(max_frequency) = cpu_stats.cores.value().and_then(|cores| cores.iter().map(|core| core.frequency).max()) {
+        rows.push((String::from("CPU Clock"), format!("{} MHz", max_frequency)));
+    }
+    rows.push((String::from("CPU Temperature"), cpu_stats.temperature.format(|temp| units.temperature.format(*temp, 1))));
+    rows.push((String::from("CPU Fan"), cpu_stats.fan_speed.format(|rpm| format!("{} RPM", rpm))));
+
+    rows.push((
+        String::from("RAM Usage"),
+        snapshot.memory_stats.format(|memory_stats| format!(
+            "{:.1}/{:.1} GB ({}%)",
+            memory_stats.used_memory, memory_stats.total_memory, memory_stats.used_memory_percentage
+        )),
+    ));
+    rows.push((
+        String::from("Swap Usage"),
+        snapshot.memory_stats.format(|memory_stats| format!("{:.1}/{:.1} GB", memory_stats.used_swap, memory_stats.total_swap)),
+    ));
+
+    rows
+}
+
+/// `rows` as aligned `label  value` lines.
+pub fn text(snapshot: &Snapshot, units: &UnitsConfig) -> String {
+    let rows = rows(snapshot, units);
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or_default();
+    rows.iter()
+        .map(|(label, value)| format!("{:width$}  {}\n", label, value))
+        .collect()
+}
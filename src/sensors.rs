@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HWMON_DIR: &str = "/sys/class/hwmon";
+const THERMAL_DIR: &str = "/sys/class/thermal";
+
+/// One temperature channel, labelled like `sensors` does: `chip` is the hwmon driver name
+/// (e.g. `k10temp`, `coretemp`) and `label` the channel label (e.g. `Tctl`, `Package id 0`).
+pub struct TemperatureReading {
+    pub chip: String,
+    pub label: String,
+    pub celsius: f32,
+}
+
+impl TemperatureReading {
+    pub fn full_label(&self) -> String {
+        full_label(&self.chip, &self.label)
+    }
+}
+
+/// One fan tachometer channel; `label` comes from `fan*_label` or defaults to `fanN`.
+pub struct FanReading {
+    pub chip: String,
+    pub label: String,
+    pub rpm: u32,
+}
+
+impl FanReading {
+    pub fn full_label(&self) -> String {
+        full_label(&self.chip, &self.label)
+    }
+}
+
+fn full_label(chip: &str, label: &str) -> String {
+    if label.is_empty() {
+        chip.to_string()
+    } else {
+        format!("{} {}", chip, label)
+    }
+}
+
+/// Reads every hwmon `temp*_input` channel, falling back to thermal zones when hwmon has none.
+pub fn read_temperatures() -> Vec<TemperatureReading> {
+    let mut readings = Vec::new();
+
+    for chip_dir in sorted_entries(Path::new(HWMON_DIR)) {
+        let chip = read_trimmed(&chip_dir.join("name")).unwrap_or_default();
+        for (id, input) in channels(&chip_dir, "temp") {
+            let Some(millidegrees) = read_trimmed(&input).and_then(|value| value.parse::<i64>().ok()) else {
+                continue;
+            };
+
+            readings.push(TemperatureReading {
+                chip: chip.clone(),
+                label: read_trimmed(&chip_dir.join(format!("temp{id}_label"))).unwrap_or_else(|| format!("temp{id}")),
+                celsius: millidegrees as f32 / 1000.0,
+            });
+        }
+    }
+
+    if readings.is_empty() {
+        for zone_dir in sorted_entries(Path::new(THERMAL_DIR)) {
+            let Some(millidegrees) = read_trimmed(&zone_dir.join("temp")).and_then(|value| value.parse::<i64>().ok()) else {
+                continue;
+            };
+
+            readings.push(TemperatureReading {
+                chip: read_trimmed(&zone_dir.join("type")).unwrap_or_default(),
+                label: String::new(),
+                celsius: millidegrees as f32 / 1000.0,
+            });
+        }
+    }
+
+    readings
+}
+
+/// Reads every hwmon `fan*_input` channel.
+pub fn read_fans() -> Vec<FanReading> {
+    let mut readings = Vec::new();
+
+    for chip_dir in sorted_entries(Path::new(HWMON_DIR)) {
+        let chip = read_trimmed(&chip_dir.join("name")).unwrap_or_default();
+        for (id, input) in channels(&chip_dir, "fan") {
+            let Some(rpm) = read_trimmed(&input).and_then(|value| value.parse().ok()) else {
+                continue;
+            };
+
+            readings.push(FanReading {
+                chip: chip.clone(),
+                label: read_trimmed(&chip_dir.join(format!("fan{id}_label"))).unwrap_or_else(|| format!("fan{id}")),
+                rpm,
+            });
+        }
+    }
+
+    readings
+}
+
+/// Lists `<prefix><id>_input` files in `chip_dir`, ordered by id.
+fn channels(chip_dir: &Path, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let mut channels: Vec<_> = sorted_entries(chip_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let file_name = path.file_name()?.to_str()?;
+            let id = file_name.strip_prefix(prefix)?.strip_suffix("_input")?.parse().ok()?;
+            Some((id, path))
+        })
+        .collect();
+    channels.sort_by_key(|(id, _)| *id);
+    channels
+}
+
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
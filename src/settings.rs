@@ -0,0 +1,131 @@
+use crate::config::{Config, HotkeyAction, Panel, TemperatureUnit, Theme, MAX_HISTORY_SECONDS};
+use eframe::egui;
+
+pub enum SettingsAction {
+    None,
+    Save,
+    Cancel,
+}
+
+/// Edits a copy of the config; nothing takes effect until it is saved.
+pub struct SettingsView {
+    pub draft: Config,
+    pub error: Option<String>,
+}
+
+impl SettingsView {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            draft: config.clone(),
+            error: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> SettingsAction {
+        let mut action = SettingsAction::None;
+
+        ui.horizontal(|ui| {
+            ui.label("Settings");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Cancel").clicked() {
+                    action = SettingsAction::Cancel;
+                }
+                if ui.button("Save").clicked() {
+                    action = SettingsAction::Save;
+                }
+            });
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::from_rgb(220, 60, 60), error);
+        }
+
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::CollapsingHeader::new("Panels").default_open(true).show(ui, |ui| {
+                self.show_panels(ui);
+            });
+
+            egui::CollapsingHeader::new("General").default_open(true).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Refresh:");
+                    ui.add(egui::DragValue::new(&mut self.draft.refresh_interval_ms).range(100..=10000).suffix(" ms"));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("History:");
+                    ui.add(egui::DragValue::new(&mut self.draft.history_seconds).range(1..=MAX_HISTORY_SECONDS).suffix(" s"));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Temperature:");
+                    ui.selectable_value(&mut self.draft.units.temperature, TemperatureUnit::Celsius, "°C");
+                    ui.selectable_value(&mut self.draft.units.temperature, TemperatureUnit::Fahrenheit, "°F");
+                });
+            });
+
+            egui::CollapsingHeader::new("Appearance").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Theme:");
+                    ui.selectable_value(&mut self.draft.theme.mode, Theme::Dark, "Dark");
+                    ui.selectable_value(&mut self.draft.theme.mode, Theme::Light, "Light");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Opacity:");
+                    ui.add(egui::Slider::new(&mut self.draft.theme.background_alpha, 0..=255));
+                });
+            });
+
+            egui::CollapsingHeader::new("Hotkeys").show(ui, |ui| {
+                for action in HotkeyAction::ALL {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}:", action.label()));
+                        ui.text_edit_singleline(self.draft.hotkeys.entry(action).or_default());
+                    });
+                }
+            });
+        });
+
+        action
+    }
+
+    /// Checkbox per panel plus buttons to move enabled panels up and down.
+    fn show_panels(&mut self, ui: &mut egui::Ui) {
+        let panels = &mut self.draft.panels;
+        let mut hidden = None;
+        let mut shown = None;
+        let mut move_up = None;
+
+        for (index, panel) in panels.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut true, panel.label()).changed() {
+                    hidden = Some(index);
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add_enabled(index + 1 < panels.len(), egui::Button::new("v").small()).clicked() {
+                        move_up = Some(index + 1);
+                    }
+                    if ui.add_enabled(index > 0, egui::Button::new("^").small()).clicked() {
+                        move_up = Some(index);
+                    }
+                });
+            });
+        }
+
+        for panel in Panel::ALL.into_iter().filter(|panel| !panels.contains(panel)) {
+            if ui.checkbox(&mut false, panel.label()).changed() {
+                shown = Some(panel);
+            }
+        }
+
+        if let Some(index) = move_up {
+            panels.swap(index - 1, index);
+        } else if let Some(index) = hidden {
+            panels.remove(index);
+        } else if let Some(panel) = shown {
+            panels.push(panel);
+        }
+    }
+}